@@ -0,0 +1,15 @@
+-- Commands, or whole categories of commands, that have been turned off in a guild
+CREATE TABLE disabled_commands (
+    guild_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (guild_id, name)
+);
+
+-- Channels and roles that a command, or category, is limited to in a guild
+CREATE TABLE command_restrictions (
+    guild_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('channel', 'role')),
+    target_id INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, name, kind, target_id)
+);
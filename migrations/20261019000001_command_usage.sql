@@ -0,0 +1,15 @@
+-- How often commands are used, aggregated by the hour they were run in
+CREATE TABLE command_usage (
+    -- Unix timestamp of the start of the hour
+    hour INTEGER NOT NULL,
+    command TEXT NOT NULL,
+    -- 0 for commands run in DMs
+    guild_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    invocations INTEGER NOT NULL,
+    errors INTEGER NOT NULL,
+    total_latency_ms INTEGER NOT NULL,
+    PRIMARY KEY (hour, command, guild_id, user_id)
+);
+
+CREATE INDEX command_usage_hour ON command_usage (hour);
@@ -0,0 +1,10 @@
+-- Songs that were queued when the bot shut down, restored when it starts again
+CREATE TABLE saved_queues (
+    guild_id INTEGER NOT NULL,
+    channel_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    query TEXT NOT NULL,
+    -- Whether the query is searched for rather than being a link
+    search INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, position)
+);
@@ -0,0 +1,8 @@
+-- Cooldowns that a guild has changed from the config for a command, or category, in seconds
+CREATE TABLE cooldown_overrides (
+    guild_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    bucket TEXT NOT NULL CHECK (bucket IN ('user', 'guild')),
+    seconds INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, name, bucket)
+);
@@ -0,0 +1,14 @@
+-- Answers that a guild has added to 8ball, picked in proportion to their weight
+CREATE TABLE ball8_answers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    weight INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('positive', 'neutral', 'negative'))
+);
+CREATE INDEX ball8_answers_guild ON ball8_answers (guild_id);
+
+-- Guilds that only want their own answers, without the built-in ones
+CREATE TABLE ball8_builtin_disabled (
+    guild_id INTEGER PRIMARY KEY NOT NULL
+);
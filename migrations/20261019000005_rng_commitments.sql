@@ -0,0 +1,9 @@
+-- Seeds that guilds in verify mode have committed to, so a restart doesn't lose them before the reveal
+CREATE TABLE rng_commitments (
+    guild_id INTEGER PRIMARY KEY NOT NULL,
+    seed BLOB NOT NULL,
+    -- How many commands have used the seed
+    uses INTEGER NOT NULL DEFAULT 0,
+    -- How far the generator got, as text since it can be larger than an INTEGER
+    word_pos TEXT NOT NULL DEFAULT '0'
+);
@@ -0,0 +1,6 @@
+-- Stations set with `radio on`, so the radio comes back on after a restart
+CREATE TABLE radio_stations (
+    guild_id INTEGER PRIMARY KEY NOT NULL,
+    channel_id INTEGER NOT NULL,
+    station TEXT NOT NULL
+);
@@ -0,0 +1,202 @@
+#[cfg(feature = "database")]
+use poise::serenity_prelude::Mentionable;
+
+#[cfg(feature = "database")]
+use crate::db::command_settings::{self, Restriction};
+use crate::{cooldowns, Context, Data, Error};
+
+/// Only lets developers, or the owner of the bot's application, run a command
+///
+/// This is used instead of `owners_only` as the developers can change when the config is reloaded
+#[cfg(any(feature = "dev", feature = "utils"))]
+pub async fn developer_only(ctx: Context<'_>) -> Result<bool, Error> {
+    if is_developer(ctx) {
+        return Ok(true);
+    }
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content("Only bot developers can call this command")
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(false)
+}
+
+/// Set as the `custom_data` of commands that use [`developer_only`], so the help can hide them
+#[cfg_attr(not(any(feature = "dev", feature = "utils")), allow(dead_code))]
+pub struct DeveloperOnly;
+
+/// Whether the command is marked with [`DeveloperOnly`]
+pub fn is_developer_command(command: &poise::Command<Data, Error>) -> bool {
+    command.custom_data.is::<DeveloperOnly>()
+}
+
+/// Whether the user is a developer, or the owner of the bot's application
+pub fn is_developer(ctx: Context<'_>) -> bool {
+    let user_id = ctx.author().id;
+    ctx.framework().options().owners.contains(&user_id) || ctx.data().is_developer(user_id)
+}
+
+/// The outcome of [`command_check`], kept in the invocation data until `pre_command` replaces it
+struct CheckResult(bool);
+
+/// Runs before every command, checking the guild's command settings and the cooldowns
+///
+/// Poise runs this again for each parent of a subcommand, so the first result is reused instead of
+/// looking everything up and replying again
+pub async fn command_check(ctx: Context<'_>) -> Result<bool, Error> {
+    let cached = ctx.invocation_data::<CheckResult>().await.map(|r| r.0);
+    if let Some(allowed) = cached {
+        return Ok(allowed);
+    }
+
+    let allowed = run_command_check(ctx).await?;
+    ctx.set_invocation_data(CheckResult(allowed)).await;
+    Ok(allowed)
+}
+
+async fn run_command_check(ctx: Context<'_>) -> Result<bool, Error> {
+    #[cfg(feature = "database")]
+    if !command_allowed(ctx).await? {
+        return Ok(false);
+    }
+
+    cooldown(ctx).await
+}
+
+/// Stops commands that were used too recently, unless it's a developer using them
+pub async fn cooldown(ctx: Context<'_>) -> Result<bool, Error> {
+    if is_developer(ctx) {
+        return Ok(true);
+    }
+    let Some(remaining) = cooldowns::remaining(ctx).await? else {
+        return Ok(true);
+    };
+
+    // Round up, so nobody is told to wait 0 seconds
+    let seconds = remaining.as_millis().div_ceil(1000);
+    ctx.send(
+        poise::CreateReply::default()
+            .content(format!(
+                "You're using this command too quickly, try again in {}s",
+                seconds
+            ))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(false)
+}
+
+/// Categories that can't be turned off or restricted, so a guild can't lock itself out of undoing it
+#[cfg(feature = "database")]
+pub const PROTECTED_CATEGORIES: &[&str] = &["admin"];
+
+/// Stops commands that the guild has turned off, or restricted to other channels or roles
+///
+/// Rules are stored against the top level command, so they cover all of its subcommands, or against
+/// the command's category
+#[cfg(feature = "database")]
+pub async fn command_allowed(ctx: Context<'_>) -> Result<bool, Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(true);
+    };
+
+    let command = ctx
+        .parent_commands()
+        .first()
+        .copied()
+        .unwrap_or(ctx.command());
+    let category = command.category.as_deref();
+    if category.is_some_and(|c| PROTECTED_CATEGORIES.contains(&c)) {
+        return Ok(true);
+    }
+
+    let names: Vec<&str> = std::iter::once(command.name.as_str())
+        .chain(category)
+        .collect();
+
+    if command_settings::is_disabled(guild_id, &names).await? {
+        ctx.send(
+            poise::CreateReply::default()
+                .content("This command is turned off in this server")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(false);
+    }
+
+    let mut restrictions = Vec::new();
+    for name in &names {
+        restrictions.extend(command_settings::restrictions(guild_id, name).await?);
+    }
+
+    let channels: Vec<_> = restrictions
+        .iter()
+        .filter_map(|r| match r {
+            Restriction::Channel(id) => Some(*id),
+            Restriction::Role(_) => None,
+        })
+        .collect();
+    if !channels.is_empty() && !channels.contains(&ctx.channel_id()) {
+        let mentions: Vec<_> = channels.iter().map(|id| id.mention().to_string()).collect();
+        ctx.send(
+            poise::CreateReply::default()
+                .content(format!(
+                    "This command can only be used in {}",
+                    mentions.join(", ")
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(false);
+    }
+
+    let roles: Vec<_> = restrictions
+        .iter()
+        .filter_map(|r| match r {
+            Restriction::Role(id) => Some(*id),
+            Restriction::Channel(_) => None,
+        })
+        .collect();
+    if !roles.is_empty() {
+        let has_role = ctx
+            .author_member()
+            .await
+            .is_some_and(|member| member.roles.iter().any(|role| roles.contains(role)));
+        if !has_role {
+            ctx.send(
+                poise::CreateReply::default()
+                    .content("You don't have a role that can use this command")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `developer_only` is the only check commands use, so any command with a check has to be marked
+    /// or the help would show it to everyone
+    #[test]
+    fn developer_commands_are_marked() {
+        fn walk(commands: &[poise::Command<Data, Error>]) {
+            for command in commands {
+                assert_eq!(
+                    is_developer_command(command),
+                    !command.checks.is_empty(),
+                    "{} has a check but isn't marked as developer only, or the other way around",
+                    command.qualified_name
+                );
+                walk(&command.subcommands);
+            }
+        }
+        walk(&crate::commands::all());
+    }
+}
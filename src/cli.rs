@@ -1,5 +1,7 @@
 use clap::{Args, Parser, Subcommand};
 
+use crate::config::CommandRegistration;
+
 #[derive(Parser, Debug)]
 #[command(name = "697's Discord Bot")]
 #[command(propagate_version = true)]
@@ -7,6 +9,51 @@ use clap::{Args, Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    #[command(flatten)]
+    pub log: LogOptions,
+}
+
+/// Where logs are written and how much detail they have
+#[derive(Args, Debug, Clone)]
+pub struct LogOptions {
+    /// Which logs to show, either a level such as debug or filter directives like RUST_LOG uses,
+    /// e.g. "info,bot697=debug". Falls back to RUST_LOG, then info
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+
+    /// How each log line is formatted
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Full)]
+    pub log_format: LogFormat,
+
+    /// Also write logs to this file, starting a new one as set by --log-rotation
+    #[arg(long, global = true)]
+    pub log_file: Option<String>,
+
+    /// How often a new log file is started, with the date appended to its name
+    #[arg(long, global = true, value_enum, default_value_t = LogRotation::Daily)]
+    pub log_rotation: LogRotation,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum LogFormat {
+    /// The default human readable format
+    Full,
+    /// Like full, but shorter
+    Compact,
+    /// Spread over multiple lines, for reading while developing
+    Pretty,
+    /// A JSON object per line, for log collectors
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    Daily,
+    /// Keep writing to the same file
+    Never,
 }
 
 #[derive(Subcommand, Debug)]
@@ -16,46 +63,151 @@ pub enum Commands {
         #[command(flatten)]
         token: TokenOptions,
 
-        /// Load the configuration from a json file
-        ///
-        /// In general, CLI arguments take precedence over configuration file values
-        ///
-        /// Where possible, configuration file values will be used in addition to CLI arguments
-        #[arg(long)]
-        config_file: Option<String>,
+        #[command(flatten)]
+        config: ConfigArgs,
 
-        /// Set a prefix for bot commands
-        #[arg(long, short, required_unless_present = "config_file")]
-        prefix: Option<String>,
+        #[command(flatten)]
+        shards: ShardOptions,
+    },
+    /// Check the config for problems and print the config the bot would run with
+    CheckConfig {
+        #[command(flatten)]
+        config: ConfigArgs,
+    },
+    /// Register the bot's application commands with Discord, without running the bot
+    Register {
+        #[command(flatten)]
+        token: TokenOptions,
 
-        /// Set additional prefixes
-        #[arg(long)]
-        extra_prefix: Vec<String>,
+        #[command(flatten)]
+        config: ConfigArgs,
 
-        /// Count mentions of the bot as a valid prefix
-        #[arg(long)]
-        mention_as_prefix: bool,
+        /// Where to register the commands, instead of the config's command_registration
+        #[arg(long, value_enum)]
+        mode: Option<CommandRegistration>,
 
-        /// Allow the bot to trigger commands from its own messages. The bot must allow bot messages for this to work.
+        /// Register the commands even if they haven't changed
         #[arg(long)]
-        allow_self_messages: bool,
+        force: bool,
+    },
+    /// Write a starter config file
+    Init {
+        /// Where to write the config, the format is picked from the extension (.json, .toml, .yaml)
+        #[arg(default_value = "config.toml")]
+        path: String,
 
-        /// Allow other bots to trigger command
-        #[arg(long)]
-        allow_bot_messages: bool,
+        /// Ask for the prefix, developers, token and database instead of using placeholders
+        #[arg(long, short)]
+        interactive: bool,
 
-        /// Make commands case sensitive
+        /// Overwrite the file if it already exists
         #[arg(long)]
-        case_sensitive: bool,
+        force: bool,
+    },
+}
 
-        // Allow users with given IDs to run developer commands
-        #[arg(long)]
-        developer_id: Vec<String>,
+/// Options that make up the bot's configuration
+///
+/// Settings are taken from, in order of priority:
+/// 1. CLI arguments
+/// 2. Environment variables, named after the argument with a `BOT697_` prefix (e.g. `BOT697_CASE_SENSITIVE=true`)
+/// 3. The config file
+/// 4. Defaults
+///
+/// Lists, such as prefixes and developers, are combined from all of these instead
+///
+/// These are kept after startup so the config file can be reloaded with the same overrides
+#[derive(Args, Debug, Clone)]
+pub struct ConfigArgs {
+    /// Load the configuration from a file
+    ///
+    /// JSON, TOML (.toml) and YAML (.yaml or .yml) files are supported. Strings in the file can
+    /// reference environment variables using ${VAR}
+    ///
+    /// In general, CLI arguments take precedence over configuration file values
+    ///
+    /// Where possible, configuration file values will be used in addition to CLI arguments
+    #[arg(long)]
+    pub config_file: Option<String>,
 
-        // Allow developer commands to run in the given guilds
-        #[arg(long)]
-        developer_guild: Vec<String>,
-    },
+    /// Set a prefix for bot commands
+    #[arg(long, short)]
+    pub prefix: Option<String>,
+
+    /// Set additional prefixes
+    #[arg(long)]
+    pub extra_prefix: Vec<String>,
+
+    /// Count mentions of the bot as a valid prefix
+    #[arg(long, overrides_with = "no_mention_as_prefix")]
+    pub mention_as_prefix: bool,
+
+    /// Don't count mentions of the bot as a valid prefix
+    #[arg(long, overrides_with = "mention_as_prefix")]
+    pub no_mention_as_prefix: bool,
+
+    /// Allow the bot to trigger commands from its own messages. The bot must allow bot messages for this to work.
+    #[arg(long, overrides_with = "no_allow_self_messages")]
+    pub allow_self_messages: bool,
+
+    /// Don't allow the bot to trigger commands from its own messages
+    #[arg(long, overrides_with = "allow_self_messages")]
+    pub no_allow_self_messages: bool,
+
+    /// Allow other bots to trigger command
+    #[arg(long, overrides_with = "no_allow_bot_messages")]
+    pub allow_bot_messages: bool,
+
+    /// Don't allow other bots to trigger commands
+    #[arg(long, overrides_with = "allow_bot_messages")]
+    pub no_allow_bot_messages: bool,
+
+    /// Make commands case sensitive
+    #[arg(long, overrides_with = "no_case_sensitive")]
+    pub case_sensitive: bool,
+
+    /// Make commands case insensitive
+    #[arg(long, overrides_with = "case_sensitive")]
+    pub no_case_sensitive: bool,
+
+    // Allow users with given IDs to run developer commands
+    #[arg(long)]
+    pub developer_id: Vec<String>,
+
+    // Allow developer commands to run in the given guilds
+    #[arg(long)]
+    pub developer_guild: Vec<String>,
+}
+
+impl ConfigArgs {
+    /// Whether `--mention-as-prefix` or `--no-mention-as-prefix` was given
+    pub fn mention_as_prefix(&self) -> Option<bool> {
+        flag(self.mention_as_prefix, self.no_mention_as_prefix)
+    }
+
+    /// Whether `--allow-self-messages` or `--no-allow-self-messages` was given
+    pub fn allow_self_messages(&self) -> Option<bool> {
+        flag(self.allow_self_messages, self.no_allow_self_messages)
+    }
+
+    /// Whether `--allow-bot-messages` or `--no-allow-bot-messages` was given
+    pub fn allow_bot_messages(&self) -> Option<bool> {
+        flag(self.allow_bot_messages, self.no_allow_bot_messages)
+    }
+
+    /// Whether `--case-sensitive` or `--no-case-sensitive` was given
+    pub fn case_sensitive(&self) -> Option<bool> {
+        flag(self.case_sensitive, self.no_case_sensitive)
+    }
+}
+
+/// Combines a `--flag`/`--no-flag` pair, returning `None` if neither was given
+fn flag(yes: bool, no: bool) -> Option<bool> {
+    match (yes, no) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
 }
 
 #[derive(Args, Debug)]
@@ -74,4 +226,122 @@ pub struct TokenOptions {
     /// Read the token from a file
     #[arg(long)]
     pub token_file: Option<String>,
+
+    /// Read the token from a systemd credential with the given name
+    ///
+    /// Credentials are read from $CREDENTIALS_DIRECTORY. If no other option is used, a credential
+    /// named discord_token is used when it exists
+    #[arg(long)]
+    pub token_credential: Option<String>,
+}
+
+/// How the bot's guilds are split between gateway connections
+#[derive(Args, Debug, Clone, Copy)]
+pub struct ShardOptions {
+    /// How many shards to run: auto for the number Discord recommends, or a number. A single
+    /// shard is used when this isn't set
+    #[arg(long)]
+    pub shards: Option<ShardCount>,
+
+    /// Only run this range of shards, e.g. 0-3 for the first four, so the shards can be split
+    /// between processes. Needs --shards to be a number
+    #[arg(long, requires = "shards")]
+    pub shard_range: Option<ShardRange>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ShardCount {
+    Auto,
+    Fixed(u32),
+}
+
+impl std::str::FromStr for ShardCount {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(ShardCount::Auto);
+        }
+        match s.parse() {
+            Ok(0) => Err("there must be at least one shard".to_string()),
+            Ok(n) => Ok(ShardCount::Fixed(n)),
+            Err(_) => Err("expected auto or a number".to_string()),
+        }
+    }
+}
+
+/// A range of shard IDs, including both ends
+#[derive(Debug, Clone, Copy)]
+pub struct ShardRange {
+    pub first: u32,
+    pub last: u32,
+}
+
+impl std::str::FromStr for ShardRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (first, last) = s
+            .split_once('-')
+            .ok_or_else(|| "expected a range like 0-3".to_string())?;
+        let first: u32 = first
+            .trim()
+            .parse()
+            .map_err(|_| format!("{:?} isn't a shard ID", first))?;
+        let last: u32 = last
+            .trim()
+            .parse()
+            .map_err(|_| format!("{:?} isn't a shard ID", last))?;
+        if first > last {
+            return Err("the first shard must come before the last".to_string());
+        }
+        Ok(ShardRange { first, last })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Args {
+        #[command(flatten)]
+        config: ConfigArgs,
+    }
+
+    fn config_args(args: &[&str]) -> ConfigArgs {
+        Args::parse_from(std::iter::once("bot").chain(args.iter().copied())).config
+    }
+
+    #[test]
+    fn cli_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn flag_combines_both_arguments() {
+        assert_eq!(flag(true, false), Some(true));
+        assert_eq!(flag(false, true), Some(false));
+        assert_eq!(flag(false, false), None);
+    }
+
+    #[test]
+    fn reads_flag_pairs() {
+        let args = config_args(&["--case-sensitive", "--no-mention-as-prefix"]);
+        assert_eq!(args.case_sensitive(), Some(true));
+        assert_eq!(args.mention_as_prefix(), Some(false));
+        assert_eq!(args.allow_self_messages(), None);
+        assert_eq!(args.allow_bot_messages(), None);
+    }
+
+    #[test]
+    fn last_flag_wins() {
+        let args = config_args(&["--allow-bot-messages", "--no-allow-bot-messages"]);
+        assert_eq!(args.allow_bot_messages(), Some(false));
+
+        let args = config_args(&["--no-allow-self-messages", "--allow-self-messages"]);
+        assert_eq!(args.allow_self_messages(), Some(true));
+    }
 }
@@ -0,0 +1,328 @@
+use poise::serenity_prelude::{self as serenity, Mentionable};
+
+use crate::{
+    checks::PROTECTED_CATEGORIES,
+    cooldowns::Bucket,
+    db::{
+        command_settings::{self, Restriction},
+        cooldowns,
+    },
+    Context, Data, Error,
+};
+
+/// The commands in this module
+pub fn commands() -> Vec<poise::Command<Data, Error>> {
+    vec![command_settings()]
+}
+
+/// Turns commands on or off in this server, limits where they can be used or sets their cooldowns
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "commands",
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("enable", "disable", "list", "restrict", "unrestrict", "cooldown")
+)]
+pub async fn command_settings(ctx: Context<'_>) -> Result<(), Error> {
+    list_inner(ctx).await
+}
+
+/// Turns a command, or a category of commands, back on
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn enable(
+    ctx: Context<'_>,
+    #[description = "the command or category to turn on"]
+    #[autocomplete = "autocomplete_name"]
+    name: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+    let Some(name) = resolve(ctx, &name).await? else {
+        return Ok(());
+    };
+
+    command_settings::set_disabled(guild_id, &name, false).await?;
+    ctx.reply(format!("Turned on `{}`", name)).await?;
+    Ok(())
+}
+
+/// Turns off a command, or a category of commands, in this server
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn disable(
+    ctx: Context<'_>,
+    #[description = "the command or category to turn off"]
+    #[autocomplete = "autocomplete_name"]
+    name: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+    let Some(name) = resolve(ctx, &name).await? else {
+        return Ok(());
+    };
+
+    command_settings::set_disabled(guild_id, &name, true).await?;
+    ctx.reply(format!("Turned off `{}`", name)).await?;
+    Ok(())
+}
+
+/// Lists the commands that are turned off, restricted or have their own cooldowns in this server
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    list_inner(ctx).await
+}
+
+/// Limits a command, or a category of commands, to a channel or role
+///
+/// A command restricted to several channels can be used in any of them, and likewise for roles
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn restrict(
+    ctx: Context<'_>,
+    #[description = "the command or category to restrict"]
+    #[autocomplete = "autocomplete_name"]
+    name: String,
+    #[description = "a channel the command can be used in"] channel: Option<serenity::GuildChannel>,
+    #[description = "a role that can use the command"] role: Option<serenity::Role>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+    if channel.is_none() && role.is_none() {
+        ctx.reply("Give a channel or role to restrict the command to")
+            .await?;
+        return Ok(());
+    }
+    let Some(name) = resolve(ctx, &name).await? else {
+        return Ok(());
+    };
+
+    let mut added = Vec::new();
+    if let Some(channel) = channel {
+        command_settings::add_restriction(guild_id, &name, Restriction::Channel(channel.id))
+            .await?;
+        added.push(channel.mention().to_string());
+    }
+    if let Some(role) = role {
+        command_settings::add_restriction(guild_id, &name, Restriction::Role(role.id)).await?;
+        added.push(role.mention().to_string());
+    }
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(format!("Restricted `{}` to {}", name, added.join(" and ")))
+            .allowed_mentions(serenity::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Removes every channel and role restriction from a command, or a category of commands
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn unrestrict(
+    ctx: Context<'_>,
+    #[description = "the command or category to unrestrict"]
+    #[autocomplete = "autocomplete_name"]
+    name: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+    let Some(name) = resolve(ctx, &name).await? else {
+        return Ok(());
+    };
+
+    let removed = command_settings::clear_restrictions(guild_id, &name).await?;
+    if removed == 0 {
+        ctx.reply(format!("`{}` isn't restricted", name)).await?;
+    } else {
+        ctx.reply(format!("Removed the restrictions on `{}`", name))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Sets how long a command, or a category of commands, has to wait between uses in this server
+///
+/// Leave out the seconds to go back to the cooldown from the bot's config, or use 0 for no cooldown
+///
+/// Example: commands cooldown play user 10
+/// Example: commands cooldown rng guild 5
+/// Example: commands cooldown play user
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn cooldown(
+    ctx: Context<'_>,
+    #[description = "the command or category to set the cooldown of"]
+    #[autocomplete = "autocomplete_name"]
+    name: String,
+    #[description = "whether the cooldown is for each user, or the whole server"] bucket: Bucket,
+    #[description = "the seconds to wait between uses"] seconds: Option<u64>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+    let Some(name) = resolve(ctx, &name).await? else {
+        return Ok(());
+    };
+
+    cooldowns::set_override(guild_id, &name, bucket, seconds).await?;
+    let msg = match seconds {
+        Some(0) => format!("Removed the {} cooldown of `{}`", bucket.as_str(), name),
+        Some(seconds) => format!(
+            "`{}` now has a {} cooldown of {}s",
+            name,
+            bucket.as_str(),
+            seconds
+        ),
+        None => format!(
+            "`{}` is back to the default {} cooldown",
+            name,
+            bucket.as_str()
+        ),
+    };
+    ctx.reply(msg).await?;
+    Ok(())
+}
+
+async fn list_inner(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+
+    let disabled = command_settings::disabled(guild_id).await?;
+    let restrictions = command_settings::all_restrictions(guild_id).await?;
+
+    let disabled = if disabled.is_empty() {
+        "Nothing".to_string()
+    } else {
+        disabled
+            .iter()
+            .map(|name| format!("`{}`", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let restrictions = if restrictions.is_empty() {
+        "Nothing".to_string()
+    } else {
+        restrictions
+            .iter()
+            .map(|(name, restriction)| match restriction {
+                Restriction::Channel(id) => format!("`{}` in {}", name, id.mention()),
+                Restriction::Role(id) => format!("`{}` for {}", name, id.mention()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let cooldowns = cooldowns::all_overrides(guild_id).await?;
+    let cooldowns = if cooldowns.is_empty() {
+        "Nothing".to_string()
+    } else {
+        cooldowns
+            .iter()
+            .map(|(name, bucket, seconds)| {
+                format!("`{}` {}s per {}", name, seconds, bucket.as_str())
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = serenity::CreateEmbed::default()
+        .title("Command settings")
+        .field("Turned off", disabled, false)
+        .field("Restricted", restrictions, false)
+        .field("Cooldowns", cooldowns, false)
+        .field("Categories", categories(ctx).join(", "), false);
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// The categories that commands can be turned off, restricted or given cooldowns by
+fn categories(ctx: Context<'_>) -> Vec<String> {
+    let mut categories: Vec<_> = ctx
+        .framework()
+        .options()
+        .commands
+        .iter()
+        .filter_map(|c| c.category.clone())
+        .filter(|c| !PROTECTED_CATEGORIES.contains(&c.as_str()))
+        .collect();
+    categories.sort();
+    categories.dedup();
+    categories
+}
+
+/// Finds the command or category with the given name, replying with why if it can't be changed
+async fn resolve(ctx: Context<'_>, name: &str) -> Result<Option<String>, Error> {
+    let name = name.trim().to_lowercase();
+
+    if categories(ctx).contains(&name) {
+        return Ok(Some(name));
+    }
+
+    let command = ctx
+        .framework()
+        .options()
+        .commands
+        .iter()
+        .find(|c| c.name == name || c.aliases.contains(&name));
+    match command {
+        Some(c)
+            if c.category
+                .as_deref()
+                .is_some_and(|c| PROTECTED_CATEGORIES.contains(&c)) =>
+        {
+            ctx.reply(format!("`{}` can't be turned off or restricted", c.name))
+                .await?;
+            Ok(None)
+        }
+        Some(c) => Ok(Some(c.name.clone())),
+        None => {
+            ctx.reply(format!("There's no command or category called `{}`", name))
+                .await?;
+            Ok(None)
+        }
+    }
+}
+
+async fn autocomplete_name(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let commands = ctx
+        .framework()
+        .options()
+        .commands
+        .iter()
+        .filter(|c| {
+            !c.category
+                .as_deref()
+                .is_some_and(|c| PROTECTED_CATEGORIES.contains(&c))
+        })
+        .map(|c| c.name.clone());
+
+    categories(ctx)
+        .into_iter()
+        .chain(commands)
+        .filter(|name| name.starts_with(&partial.to_lowercase()))
+        .take(25)
+        .collect()
+}
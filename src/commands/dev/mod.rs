@@ -1,13 +1,37 @@
 use poise::serenity_prelude;
 
-use crate::{Context, Error};
+use crate::{Context, Data, Error};
 
 pub mod register;
+pub mod reload;
 pub mod say;
+pub mod shards;
+pub mod shutdown;
+#[cfg(feature = "database")]
+pub mod stats;
 
-#[poise::command(prefix_command, owners_only)]
+/// The commands in this module
+pub fn commands() -> Vec<poise::Command<Data, Error>> {
+    vec![
+        register::devregister(),
+        say::say(),
+        dumpconfig(),
+        reload::reload(),
+        shutdown::shutdown(),
+        shutdown::restart(),
+        shards::shards(),
+        #[cfg(feature = "database")]
+        stats::stats(),
+    ]
+}
+
+#[poise::command(
+    prefix_command,
+    check = "crate::checks::developer_only",
+    custom_data = "crate::checks::DeveloperOnly"
+)]
 pub async fn dumpconfig(ctx: Context<'_>) -> Result<(), Error> {
-    let config = ctx.data().config.clone();
+    let config = ctx.data().config.read().unwrap().clone();
     let config = serde_json::to_string_pretty(&config).unwrap();
     if config.len() > 1950 {
         ctx.send(
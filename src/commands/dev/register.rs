@@ -1,14 +1,31 @@
-use crate::{Context, Error};
+use crate::{config::CommandRegistration, registration, Context, Error};
 
-#[poise::command(prefix_command, owners_only)]
+/// Registers the application commands in this guild, which has to be a developer guild
+///
+/// poise's register buttons are not used, as they only let the application owner through
+#[poise::command(
+    prefix_command,
+    check = "crate::checks::developer_only",
+    custom_data = "crate::checks::DeveloperOnly"
+)]
 pub async fn devregister(ctx: Context<'_>) -> Result<(), Error> {
-    if ctx
+    let Some(guild_id) = ctx
         .guild_id()
-        .is_some_and(|id| ctx.data().developer_guilds.contains(&id))
-    {
-        poise::builtins::register_application_commands_buttons(ctx).await?;
-    } else {
+        .filter(|&id| ctx.data().is_developer_guild(id))
+    else {
         ctx.reply("Rerun the command in a testing guild.").await?;
-    }
+        return Ok(());
+    };
+
+    let commands = &ctx.framework().options().commands;
+    registration::register(
+        ctx.http(),
+        commands,
+        CommandRegistration::DeveloperGuilds,
+        [guild_id],
+        true,
+    )
+    .await?;
+    ctx.reply("Registered the commands in this guild.").await?;
     Ok(())
 }
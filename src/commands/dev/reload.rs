@@ -0,0 +1,38 @@
+use crate::{config, Context, Error};
+
+/// Reloads the config file
+///
+/// The CLI arguments the bot was started with are applied again on top of the new config
+#[poise::command(
+    prefix_command,
+    check = "crate::checks::developer_only",
+    custom_data = "crate::checks::DeveloperOnly"
+)]
+pub async fn reload(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+
+    match config::reload(&data.config, &data.config_args) {
+        Ok(changes) if changes.is_empty() => {
+            ctx.reply("Reloaded the config, nothing changed.").await?;
+        }
+        Ok(changes) => {
+            tracing::info!("config reloaded by: {}", ctx.author().id);
+            let changes: String = changes
+                .into_iter()
+                .map(|change| format!("- {}\n", change))
+                .collect();
+            ctx.reply(format!("Reloaded the config:\n```\n{}```", changes))
+                .await?;
+        }
+        Err(e) => {
+            tracing::warn!("failed to reload the config: {}", e);
+            ctx.reply(format!(
+                "Couldn't reload the config, keeping the current one: {}",
+                e
+            ))
+            .await?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,6 +1,10 @@
 use crate::{Context, Error};
 
-#[poise::command(prefix_command, owners_only)]
+#[poise::command(
+    prefix_command,
+    check = "crate::checks::developer_only",
+    custom_data = "crate::checks::DeveloperOnly"
+)]
 pub async fn say(
     ctx: Context<'_>,
     #[description = "the message to send"]
@@ -0,0 +1,53 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{Context, Error};
+
+/// Shows the connection stage, latency and guild count of each shard this process runs
+#[poise::command(
+    prefix_command,
+    check = "crate::checks::developer_only",
+    custom_data = "crate::checks::DeveloperOnly"
+)]
+pub async fn shards(ctx: Context<'_>) -> Result<(), Error> {
+    let shard_count = ctx.cache().shard_count();
+    let mut guilds: HashMap<u32, usize> = HashMap::new();
+    for guild_id in ctx.cache().guilds() {
+        *guilds.entry(guild_id.shard_id(ctx.cache())).or_default() += 1;
+    }
+
+    let mut runners: Vec<_> = ctx
+        .framework()
+        .shard_manager()
+        .runners
+        .lock()
+        .await
+        .iter()
+        .map(|(id, runner)| (id.0, runner.stage, runner.latency))
+        .collect();
+    runners.sort_by_key(|(id, _, _)| *id);
+
+    let running = runners.len();
+    let mut table = format!(
+        "{:<6} {:<14} {:>8} {:>7}\n",
+        "shard", "stage", "latency", "guilds"
+    );
+    for (id, stage, latency) in runners {
+        let latency = latency.map_or_else(|| "-".to_string(), |l| format!("{}ms", l.as_millis()));
+        writeln!(
+            table,
+            "{:<6} {:<14} {:>8} {:>7}",
+            id,
+            stage.to_string(),
+            latency,
+            guilds.get(&id).copied().unwrap_or(0)
+        )?;
+    }
+
+    let current = ctx.serenity_context().shard_id;
+    ctx.reply(format!(
+        "Running {} of {} shards, this message was handled by shard {}\n```\n{}```",
+        running, shard_count, current, table
+    ))
+    .await?;
+    Ok(())
+}
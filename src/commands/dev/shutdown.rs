@@ -0,0 +1,33 @@
+use crate::{Context, Error};
+
+/// Shuts the bot down, saving the queues and leaving every call first
+#[poise::command(
+    prefix_command,
+    check = "crate::checks::developer_only",
+    custom_data = "crate::checks::DeveloperOnly"
+)]
+pub async fn shutdown(ctx: Context<'_>) -> Result<(), Error> {
+    tracing::info!("shutdown requested by: {}", ctx.author().id);
+    ctx.reply("Shutting down.").await?;
+    stop(ctx, false);
+    Ok(())
+}
+
+/// Shuts the bot down the same way as `shutdown`, then starts it again with the same arguments
+#[poise::command(
+    prefix_command,
+    check = "crate::checks::developer_only",
+    custom_data = "crate::checks::DeveloperOnly"
+)]
+pub async fn restart(ctx: Context<'_>) -> Result<(), Error> {
+    tracing::info!("restart requested by: {}", ctx.author().id);
+    ctx.reply("Restarting.").await?;
+    stop(ctx, true);
+    Ok(())
+}
+
+/// Shuts down in the background, so the command can finish before the shards are stopped
+fn stop(ctx: Context<'_>, restart: bool) {
+    let shutdown = ctx.data().shutdown.clone();
+    tokio::spawn(async move { shutdown.run(restart).await });
+}
@@ -0,0 +1,117 @@
+use std::fmt::Write;
+
+use poise::{serenity_prelude as serenity, ChoiceParameter};
+
+use crate::{db::command_usage, error, Context, Error};
+
+#[derive(ChoiceParameter, Clone, Copy)]
+pub enum Window {
+    #[name = "24h"]
+    Day,
+    #[name = "7d"]
+    Week,
+    #[name = "30d"]
+    Month,
+}
+
+impl Window {
+    fn seconds(self) -> i64 {
+        match self {
+            Window::Day => 24 * 60 * 60,
+            Window::Week => 7 * 24 * 60 * 60,
+            Window::Month => 30 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// Shows how much each command has been used
+///
+/// Usage is counted by the hour, so the window can include up to an hour more than asked for
+#[poise::command(
+    prefix_command,
+    check = "crate::checks::developer_only",
+    custom_data = "crate::checks::DeveloperOnly"
+)]
+pub async fn stats(
+    ctx: Context<'_>,
+    #[description = "how far back to look, 24h by default"] window: Option<Window>,
+    #[description = "how many entries to show in each table, 10 by default"]
+    #[min = 1]
+    #[max = 25]
+    top: Option<u32>,
+) -> Result<(), Error> {
+    let window = window.unwrap_or(Window::Day);
+    let top = top.unwrap_or(10).clamp(1, 25);
+    let now = chrono::Utc::now().timestamp();
+    let since = (now - window.seconds()) / 3600 * 3600;
+
+    let total = command_usage::total(since).await?;
+    let commands = command_usage::top_commands(since, top).await?;
+    let guilds = command_usage::top_guilds(since, top).await?;
+    let users = command_usage::top_users(since, top).await?;
+
+    let mut command_table = format!(
+        "{:<20} {:>7} {:>6} {:>9}\n",
+        "command", "uses", "errors", "avg ms"
+    );
+    for command in &commands {
+        writeln!(
+            command_table,
+            "{:<20} {:>7} {:>6} {:>9.0}",
+            command.name, command.invocations, command.errors, command.average_latency_ms
+        )?;
+    }
+
+    let mut guild_table = String::new();
+    for (guild_id, total) in &guilds {
+        let name = ctx
+            .cache()
+            .guild(*guild_id)
+            .map_or_else(|| guild_id.to_string(), |guild| guild.name.clone());
+        writeln!(
+            guild_table,
+            "{:<30} {:>7}",
+            error::truncate(&name, 30),
+            total
+        )?;
+    }
+
+    let mut user_table = String::new();
+    for (user_id, total) in &users {
+        let name = ctx
+            .cache()
+            .user(*user_id)
+            .map_or_else(|| user_id.to_string(), |user| user.name.clone());
+        writeln!(
+            user_table,
+            "{:<30} {:>7}",
+            error::truncate(&name, 30),
+            total
+        )?;
+    }
+
+    let embed = serenity::CreateEmbed::default()
+        .title(format!("Command usage over the last {}", window.name()))
+        .field("Uses", total.invocations.to_string(), true)
+        .field("Errors", total.errors.to_string(), true)
+        .field(
+            "Average latency",
+            format!("{:.0}ms", total.average_latency_ms),
+            true,
+        )
+        .field("Top commands", code_block(&command_table), false)
+        .field("Top guilds", code_block(&guild_table), false)
+        .field("Top users", code_block(&user_table), false);
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+fn code_block(table: &str) -> String {
+    if table.trim().is_empty() {
+        "Nothing yet".to_string()
+    } else {
+        // Embed fields can be at most 1024 characters
+        format!("```\n{}```", error::truncate(table, 1000))
+    }
+}
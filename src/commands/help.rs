@@ -0,0 +1,393 @@
+use std::time::Duration;
+
+use poise::serenity_prelude as serenity;
+
+use crate::{checks, error, Context, Data, Error};
+
+type Command = poise::Command<Data, Error>;
+
+/// The categories in the order they're shown, with the title of their page
+const CATEGORIES: &[(&str, &str)] = &[
+    ("utils", "Utilities"),
+    ("rng", "Random"),
+    ("voice", "Voice"),
+    ("admin", "Server settings"),
+    ("dev", "Developer"),
+    ("help", "Help"),
+];
+
+/// How long the buttons keep working after the last press
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Lines of a command's help text starting with this are shown as examples, after the prefix
+const EXAMPLE_MARKER: &str = "Example: ";
+
+/// Shows the commands, or help for one of them
+///
+/// Without a command, the commands are listed by category with buttons to move between them
+///
+/// Example: help
+/// Example: help dice
+#[poise::command(prefix_command, slash_command, track_edits)]
+pub async fn help(
+    ctx: Context<'_>,
+    #[description = "the command to show help for"]
+    #[autocomplete = "autocomplete_command"]
+    #[rest]
+    command: Option<String>,
+) -> Result<(), Error> {
+    let commands = &ctx.framework().options().commands;
+    let categories = visible_categories(ctx);
+
+    let mut view = match command.as_deref() {
+        Some(name) => match find(ctx, name) {
+            Some(command) => View::Command(command.qualified_name.clone()),
+            None => {
+                ctx.say(format!("There's no command called `{}`", name.trim()))
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => View::Page(0),
+    };
+
+    let id = ctx.id();
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(render(ctx, &categories, &view))
+                .components(components(ctx, &categories, &view, id)),
+        )
+        .await?;
+
+    while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .filter(move |press| press.data.custom_id.starts_with(&format!("{}:", id)))
+        .timeout(TIMEOUT)
+        .await
+    {
+        let action = press.data.custom_id.split_once(':').map_or("", |(_, a)| a);
+        view = match (action, &view) {
+            ("previous", View::Page(page)) => {
+                View::Page(page.checked_sub(1).unwrap_or(categories.len()))
+            }
+            ("next", View::Page(page)) => View::Page((page + 1) % (categories.len() + 1)),
+            ("back", View::Command(name)) => {
+                let category = commands
+                    .iter()
+                    .find(|c| Some(c.name.as_str()) == name.split(' ').next())
+                    .and_then(|c| c.category.as_deref());
+                let page = categories
+                    .iter()
+                    .position(|(c, _)| Some(*c) == category)
+                    .map_or(0, |i| i + 1);
+                View::Page(page)
+            }
+            ("command", _) => match &press.data.kind {
+                serenity::ComponentInteractionDataKind::StringSelect { values } => values
+                    .first()
+                    .map_or(view, |name| View::Command(name.clone())),
+                _ => view,
+            },
+            _ => view,
+        };
+
+        press
+            .create_response(
+                ctx,
+                serenity::CreateInteractionResponse::UpdateMessage(
+                    serenity::CreateInteractionResponseMessage::new()
+                        .embed(render(ctx, &categories, &view))
+                        .components(components(ctx, &categories, &view, id)),
+                ),
+            )
+            .await?;
+    }
+
+    // The buttons stop working once the collector times out, so take them away
+    reply
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .embed(render(ctx, &categories, &view))
+                .components(vec![]),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// What the help message is showing: the overview is page 0, followed by a page per category
+enum View {
+    Page(usize),
+    Command(String),
+}
+
+fn render(ctx: Context<'_>, categories: &[(&str, &str)], view: &View) -> serenity::CreateEmbed {
+    match view {
+        View::Page(0) => overview(ctx, categories),
+        View::Page(page) => category_page(ctx, categories, *page),
+        View::Command(name) => match find(ctx, name) {
+            Some(command) => command_page(ctx, command),
+            None => serenity::CreateEmbed::default().description("That command no longer exists"),
+        },
+    }
+}
+
+fn overview(ctx: Context<'_>, categories: &[(&str, &str)]) -> serenity::CreateEmbed {
+    let mut embed = serenity::CreateEmbed::default()
+        .title("Help")
+        .description(format!(
+            "Use the buttons to look through the categories, or `{}help <command>` for more about a command",
+            ctx.prefix()
+        ));
+
+    for (category, title) in categories {
+        let names: Vec<_> = commands_in(ctx, category)
+            .map(|c| format!("`{}`", c.name))
+            .collect();
+        embed = embed.field(*title, names.join(" "), false);
+    }
+
+    embed.footer(page_footer(0, categories.len()))
+}
+
+fn category_page(
+    ctx: Context<'_>,
+    categories: &[(&str, &str)],
+    page: usize,
+) -> serenity::CreateEmbed {
+    let (category, title) = categories[page - 1];
+
+    let lines: Vec<_> = commands_in(ctx, category)
+        .map(|c| {
+            let description = c.description.as_deref().unwrap_or("No description");
+            format!("`{}{}` {}", prefix_for(ctx, c), usage(c), description)
+        })
+        .collect();
+
+    serenity::CreateEmbed::default()
+        .title(title)
+        .description(lines.join("\n"))
+        .footer(page_footer(page, categories.len()))
+}
+
+fn command_page(ctx: Context<'_>, command: &Command) -> serenity::CreateEmbed {
+    let prefix = prefix_for(ctx, command);
+    let help_text = command.help_text.as_deref().unwrap_or_default();
+    let details: Vec<_> = help_text
+        .lines()
+        .filter(|line| !line.starts_with(EXAMPLE_MARKER))
+        .collect();
+    let examples: Vec<_> = help_text
+        .lines()
+        .filter_map(|line| line.strip_prefix(EXAMPLE_MARKER))
+        .map(|example| format!("`{}{}`", prefix, example))
+        .collect();
+
+    let mut description = command
+        .description
+        .clone()
+        .unwrap_or_else(|| "No description".to_string());
+    let details = details.join("\n");
+    if !details.trim().is_empty() {
+        description = format!("{}\n\n{}", description, details.trim());
+    }
+
+    let mut embed = serenity::CreateEmbed::default()
+        .title(format!("{}{}", prefix, command.qualified_name))
+        .description(description)
+        .field("Usage", format!("`{}{}`", prefix, usage(command)), false);
+
+    if !command.parameters.is_empty() {
+        let parameters: Vec<_> = command
+            .parameters
+            .iter()
+            .map(|p| {
+                let description = p.description.as_deref().unwrap_or("no description");
+                let optional = if p.required { "" } else { " (optional)" };
+                format!("`{}` {}{}", p.name, description, optional)
+            })
+            .collect();
+        embed = embed.field("Parameters", parameters.join("\n"), false);
+    }
+
+    if !command.subcommands.is_empty() {
+        let subcommands: Vec<_> = command
+            .subcommands
+            .iter()
+            .map(|c| {
+                let description = c.description.as_deref().unwrap_or("No description");
+                format!("`{}` {}", c.name, description)
+            })
+            .collect();
+        embed = embed.field("Subcommands", subcommands.join("\n"), false);
+    }
+
+    if !command.aliases.is_empty() {
+        let aliases: Vec<_> = command.aliases.iter().map(|a| format!("`{}`", a)).collect();
+        embed = embed.field("Aliases", aliases.join(" "), true);
+    }
+
+    if !examples.is_empty() {
+        embed = embed.field("Examples", examples.join("\n"), false);
+    }
+
+    let mut notes = Vec::new();
+    if command.guild_only {
+        notes.push("Only works in servers".to_string());
+    }
+    if !command.required_permissions.is_empty() {
+        notes.push(format!(
+            "Needs the {} permission(s)",
+            command.required_permissions
+        ));
+    }
+    if command.slash_action.is_none() {
+        notes.push("Can't be used as a slash command".to_string());
+    } else if command.prefix_action.is_none() {
+        notes.push("Only works as a slash command".to_string());
+    }
+    if !notes.is_empty() {
+        embed = embed.field("Notes", notes.join("\n"), false);
+    }
+
+    embed
+}
+
+fn components(
+    ctx: Context<'_>,
+    categories: &[(&str, &str)],
+    view: &View,
+    id: u64,
+) -> Vec<serenity::CreateActionRow> {
+    match view {
+        View::Page(page) => {
+            let mut rows = vec![serenity::CreateActionRow::Buttons(vec![
+                serenity::CreateButton::new(format!("{}:previous", id)).label("Previous"),
+                serenity::CreateButton::new(format!("{}:next", id)).label("Next"),
+            ])];
+
+            let options: Vec<_> = match page.checked_sub(1).and_then(|i| categories.get(i)) {
+                Some((category, _)) => commands_in(ctx, category).collect(),
+                None => Vec::new(),
+            }
+            .into_iter()
+            .take(25)
+            .map(|c| {
+                let option = serenity::CreateSelectMenuOption::new(&c.name, &c.qualified_name);
+                match &c.description {
+                    Some(description) => option.description(error::truncate(description, 100)),
+                    None => option,
+                }
+            })
+            .collect();
+            if !options.is_empty() {
+                rows.push(serenity::CreateActionRow::SelectMenu(
+                    serenity::CreateSelectMenu::new(
+                        format!("{}:command", id),
+                        serenity::CreateSelectMenuKind::String { options },
+                    )
+                    .placeholder("Show a command"),
+                ));
+            }
+            rows
+        }
+        View::Command(_) => vec![serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new(format!("{}:back", id)).label("Back"),
+        ])],
+    }
+}
+
+/// The command's name followed by its parameters, with optional ones in square brackets
+pub fn usage(command: &Command) -> String {
+    let mut usage = command.qualified_name.clone();
+    for parameter in &command.parameters {
+        if parameter.required {
+            usage.push_str(&format!(" <{}>", parameter.name));
+        } else {
+            usage.push_str(&format!(" [{}]", parameter.name));
+        }
+    }
+    if !command.subcommands.is_empty() && command.subcommand_required {
+        usage.push_str(" <subcommand>");
+    }
+    usage
+}
+
+/// Whether the user can see the command, as developer commands are hidden from everyone else
+fn visible(ctx: Context<'_>, command: &Command) -> bool {
+    if command.hide_in_help {
+        return false;
+    }
+    !checks::is_developer_command(command) || checks::is_developer(ctx)
+}
+
+fn visible_categories(ctx: Context<'_>) -> Vec<(&'static str, &'static str)> {
+    CATEGORIES
+        .iter()
+        .copied()
+        .filter(|(category, _)| commands_in(ctx, category).next().is_some())
+        .collect()
+}
+
+fn commands_in<'a>(ctx: Context<'a>, category: &'a str) -> impl Iterator<Item = &'a Command> + 'a {
+    ctx.framework()
+        .options()
+        .commands
+        .iter()
+        .filter(move |c| c.category.as_deref() == Some(category) && visible(ctx, c))
+}
+
+/// Finds a command or subcommand by name or alias, if the user can see it
+fn find<'a>(ctx: Context<'a>, name: &str) -> Option<&'a Command> {
+    let commands = &ctx.framework().options().commands;
+    let (command, _, _) = poise::find_command(commands, name.trim(), true, &mut Vec::new())?;
+
+    // The command found borrows from the name, so walk down to it again by its qualified name
+    let mut names = command.qualified_name.split(' ');
+    let root = commands
+        .iter()
+        .find(|c| Some(c.name.as_str()) == names.next())?;
+    if !visible(ctx, root) {
+        return None;
+    }
+    names.try_fold(root, |command, name| {
+        command.subcommands.iter().find(|c| c.name == name)
+    })
+}
+
+/// The prefix to show before a command, which is / for slash commands
+fn prefix_for(ctx: Context<'_>, command: &Command) -> String {
+    let slash = ctx.prefix() == "/";
+    if slash && command.slash_action.is_none() {
+        let config = ctx.data().config.read().unwrap();
+        config
+            .prefixes
+            .iter()
+            .flatten()
+            .next()
+            .cloned()
+            .unwrap_or_default()
+    } else if !slash && command.prefix_action.is_none() {
+        "/".to_string()
+    } else {
+        ctx.prefix().to_string()
+    }
+}
+
+fn page_footer(page: usize, categories: usize) -> serenity::CreateEmbedFooter {
+    serenity::CreateEmbedFooter::new(format!("Page {} of {}", page + 1, categories + 1))
+}
+
+async fn autocomplete_command(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    ctx.framework()
+        .options()
+        .commands
+        .iter()
+        .filter(|c| visible(ctx, c))
+        .map(|c| c.name.clone())
+        .filter(|name| name.starts_with(&partial.to_lowercase()))
+        .take(25)
+        .collect()
+}
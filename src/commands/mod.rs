@@ -1,5 +1,49 @@
+use crate::{hooks, Data, Error};
+
+#[cfg(feature = "database")]
+pub mod admin;
+#[cfg(feature = "dev")]
 pub mod dev;
+pub mod help;
 pub mod ping;
+#[cfg(feature = "rng")]
 pub mod rng;
+#[cfg(feature = "utils")]
 pub mod utils;
+#[cfg(feature = "voice")]
 pub mod voice;
+
+/// Every command in the modules the bot was built with
+///
+/// Each module's commands are put in a category named after it, which is what the help groups them
+/// by and what guilds can turn off with the `commands` command
+pub fn all() -> Vec<poise::Command<Data, Error>> {
+    let mut commands = Vec::new();
+    commands.extend(in_category("help", vec![help::help()]));
+    commands.extend(in_category("utils", vec![ping::ping()]));
+    #[cfg(feature = "database")]
+    commands.extend(in_category("admin", admin::commands()));
+    #[cfg(feature = "dev")]
+    commands.extend(in_category("dev", dev::commands()));
+    #[cfg(feature = "rng")]
+    commands.extend(in_category("rng", rng::commands()));
+    #[cfg(feature = "utils")]
+    commands.extend(in_category("utils", utils::commands()));
+    #[cfg(feature = "voice")]
+    commands.extend(in_category("voice", voice::commands()));
+    hooks::instrument(&mut commands);
+    commands
+}
+
+fn in_category(
+    category: &str,
+    commands: Vec<poise::Command<Data, Error>>,
+) -> Vec<poise::Command<Data, Error>> {
+    commands
+        .into_iter()
+        .map(|mut command| {
+            command.category = Some(category.to_string());
+            command
+        })
+        .collect()
+}
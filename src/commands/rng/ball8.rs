@@ -0,0 +1,317 @@
+use poise::serenity_prelude as serenity;
+use rand::{distributions::WeightedIndex, prelude::Distribution};
+
+#[cfg(feature = "database")]
+use crate::db;
+use crate::{Context, Error};
+
+/// The most answers a guild can add
+#[cfg(feature = "database")]
+const MAX_ANSWERS: usize = 25;
+/// The longest an added answer can be
+#[cfg(feature = "database")]
+const MAX_LENGTH: usize = 100;
+/// The largest weight an added answer can have
+#[cfg(feature = "database")]
+const MAX_WEIGHT: u32 = 100;
+
+/// The answers every guild has unless it turns them off, with their weights
+const BUILTIN: &[(&str, u32, Kind)] = &[
+    ("Don't count on it", 2, Kind::Negative),
+    ("My reply is no", 2, Kind::Negative),
+    ("My sources say no", 2, Kind::Negative),
+    ("Outlook not so good", 2, Kind::Negative),
+    ("Very doubtful", 2, Kind::Negative),
+    ("Reply hazy, try again", 1, Kind::Neutral),
+    ("Ask again later", 1, Kind::Neutral),
+    ("Better not tell you now", 1, Kind::Neutral),
+    ("Cannot predict now", 1, Kind::Neutral),
+    ("Concentrate and ask again", 1, Kind::Neutral),
+    ("It is certain", 1, Kind::Positive),
+    ("It is decidedly so", 1, Kind::Positive),
+    ("Without a doubt", 1, Kind::Positive),
+    ("Yes definitely", 1, Kind::Positive),
+    ("You may rely on it", 1, Kind::Positive),
+    ("As I see it, yes", 1, Kind::Positive),
+    ("Most likely", 1, Kind::Positive),
+    ("Outlook good", 1, Kind::Positive),
+    ("Yes", 1, Kind::Positive),
+    ("Signs point to yes", 1, Kind::Positive),
+];
+
+/// Whether an answer means yes, no or neither
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    #[name = "positive"]
+    Positive,
+    #[name = "neutral"]
+    Neutral,
+    #[name = "negative"]
+    Negative,
+}
+
+#[cfg(feature = "database")]
+impl Kind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::Positive => "positive",
+            Kind::Neutral => "neutral",
+            Kind::Negative => "negative",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "positive" => Some(Kind::Positive),
+            "neutral" => Some(Kind::Neutral),
+            "negative" => Some(Kind::Negative),
+            _ => None,
+        }
+    }
+}
+
+/// Something 8ball can answer with
+#[derive(Clone, Debug)]
+pub struct Answer {
+    pub text: String,
+    /// How likely the answer is compared to the others
+    pub weight: u32,
+    /// Only shown when listing a guild's answers, which needs the database
+    #[cfg_attr(not(feature = "database"), allow(dead_code))]
+    pub kind: Kind,
+}
+
+/// Helps you make a decision
+///
+/// Answers a yes or no question
+///
+/// Example: 8ball will it rain tomorrow?
+#[allow(unused_variables)]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    aliases("8b"),
+    rename = "8ball",
+    discard_spare_arguments
+)]
+pub async fn ball8(ctx: Context<'_>, #[rest] question: Option<String>) -> Result<(), Error> {
+    let answers = answers(ctx).await?;
+    if answers.is_empty() {
+        ctx.reply("This server has turned off the built-in answers without adding any of its own")
+            .await?;
+        return Ok(());
+    }
+
+    let weights = WeightedIndex::new(answers.iter().map(|answer| answer.weight))?;
+    let index = ctx
+        .data()
+        .rng
+        .with(ctx.guild_id(), |rng| weights.sample(rng));
+    ctx.send(
+        poise::CreateReply::default()
+            .content(&answers[index].text)
+            .reply(true)
+            .allowed_mentions(serenity::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+pub(super) fn builtin_answers() -> impl Iterator<Item = Answer> {
+    BUILTIN.iter().map(|&(text, weight, kind)| Answer {
+        text: text.to_string(),
+        weight,
+        kind,
+    })
+}
+
+/// The answers 8ball can give here, which are the built-in ones and whatever the guild has added
+#[cfg_attr(not(feature = "database"), allow(unused_variables))]
+async fn answers(ctx: Context<'_>) -> Result<Vec<Answer>, Error> {
+    #[cfg(feature = "database")]
+    if let Some(guild_id) = ctx.guild_id() {
+        let mut answers = Vec::new();
+        if db::ball8::builtin_enabled(guild_id).await? {
+            answers.extend(builtin_answers());
+        }
+        let custom = db::ball8::answers(guild_id).await?;
+        answers.extend(custom.into_iter().map(|custom| custom.answer));
+        return Ok(answers);
+    }
+
+    Ok(builtin_answers().collect())
+}
+
+/// Changes the answers 8ball gives in this server
+#[cfg(feature = "database")]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    rename = "8ball-answers",
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("add", "remove", "list", "builtin")
+)]
+pub async fn ball8_answers(ctx: Context<'_>) -> Result<(), Error> {
+    list_inner(ctx).await
+}
+
+/// Adds an answer to 8ball in this server
+///
+/// The weight makes the answer more likely, the built-in answers have a weight of 1 or 2
+///
+/// Example: 8ball-answers add positive Absolutely
+/// Example: 8ball-answers add negative 3 Not a chance
+#[cfg(feature = "database")]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "whether the answer means yes, no or neither"] kind: Kind,
+    #[description = "how likely the answer is, 1 if left out"] weight: Option<u32>,
+    #[description = "the answer"]
+    #[rest]
+    text: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+    let text = text.trim().to_string();
+    let weight = weight.unwrap_or(1);
+
+    if text.is_empty() || text.chars().count() > MAX_LENGTH {
+        ctx.reply(format!(
+            "Answers have to be between 1 and {} characters",
+            MAX_LENGTH
+        ))
+        .await?;
+        return Ok(());
+    }
+    if !(1..=MAX_WEIGHT).contains(&weight) {
+        ctx.reply(format!("The weight has to be between 1 and {}", MAX_WEIGHT))
+            .await?;
+        return Ok(());
+    }
+    if db::ball8::answers(guild_id).await?.len() >= MAX_ANSWERS {
+        ctx.reply(format!(
+            "This server already has {} answers, remove one first",
+            MAX_ANSWERS
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let id = db::ball8::add(guild_id, &Answer { text, weight, kind }).await?;
+    ctx.reply(format!("Added the answer, its ID is {}", id))
+        .await?;
+    Ok(())
+}
+
+/// Removes one of the answers added to 8ball in this server
+///
+/// Example: 8ball-answers remove 3
+#[cfg(feature = "database")]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "the ID of the answer, as shown by the list"] id: i64,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+
+    if db::ball8::remove(guild_id, id).await? {
+        ctx.reply(format!("Removed answer {}", id)).await?;
+    } else {
+        ctx.reply(format!("There's no answer with the ID {}", id))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Lists the answers added to 8ball in this server
+#[cfg(feature = "database")]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    list_inner(ctx).await
+}
+
+/// Turns the built-in 8ball answers on or off in this server
+///
+/// Example: 8ball-answers builtin false
+#[cfg(feature = "database")]
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn builtin(
+    ctx: Context<'_>,
+    #[description = "whether the built-in answers are used"] enabled: bool,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+
+    db::ball8::set_builtin_enabled(guild_id, enabled).await?;
+    if enabled {
+        ctx.reply("Turned on the built-in answers").await?;
+    } else {
+        ctx.reply("Turned off the built-in answers, only the ones added here will be used")
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "database")]
+async fn list_inner(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+
+    let builtin = if db::ball8::builtin_enabled(guild_id).await? {
+        format!("On, {} answers", BUILTIN.len())
+    } else {
+        "Off".to_string()
+    };
+
+    // Grouped in the description, as a field can't hold all of the answers of one kind
+    let custom = db::ball8::answers(guild_id).await?;
+    let mut description = String::new();
+    for (kind, title) in [
+        (Kind::Positive, "Positive"),
+        (Kind::Neutral, "Neutral"),
+        (Kind::Negative, "Negative"),
+    ] {
+        let lines: Vec<_> = custom
+            .iter()
+            .filter(|custom| custom.answer.kind == kind)
+            .map(|custom| {
+                format!(
+                    "`{}` {} (weight {})",
+                    custom.id, custom.answer.text, custom.answer.weight
+                )
+            })
+            .collect();
+        if !lines.is_empty() {
+            description.push_str(&format!("**{}**\n{}\n", title, lines.join("\n")));
+        }
+    }
+    if custom.is_empty() {
+        description = "No answers have been added, use `8ball-answers add` to add some".to_string();
+    }
+
+    let embed = serenity::CreateEmbed::default()
+        .title("8ball answers")
+        .description(description)
+        .field("Built-in", builtin, false);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
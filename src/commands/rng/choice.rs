@@ -0,0 +1,314 @@
+use poise::serenity_prelude::{self as serenity, Mentionable};
+use rand::{distributions::WeightedIndex, prelude::Distribution, seq::SliceRandom, Rng};
+
+use crate::{error, Context, Error};
+
+/// The most options a list can have
+const MAX_OPTIONS: usize = 100;
+/// The largest weight an option can be given
+const MAX_WEIGHT: u32 = 1000;
+/// The most characters of an option that are repeated back, keeping the reply under Discord's limit
+const MAX_SHOWN: usize = 1900;
+/// The most characters an embed description can have
+const MAX_DESCRIPTION: usize = 4096;
+
+/// Picks one of the options, separated by `|` or commas
+///
+/// Add `*N` after an option to make it N times as likely to be picked
+///
+/// Example: choose pizza | burgers | sushi
+/// Example: choose stay in*3 | go out
+#[poise::command(slash_command, prefix_command, aliases("pick"))]
+pub async fn choose(
+    ctx: Context<'_>,
+    #[description = "the options, separated by | or commas, like a*3 | b"]
+    #[rest]
+    options: String,
+) -> Result<(), Error> {
+    let options = match parse_options(&options) {
+        Ok(options) => options,
+        Err(e) => {
+            ctx.reply(e).await?;
+            return Ok(());
+        }
+    };
+
+    let weights = WeightedIndex::new(options.iter().map(|(_, weight)| *weight))?;
+    let index = ctx
+        .data()
+        .rng
+        .with(ctx.guild_id(), |rng| weights.sample(rng));
+    ctx.send(
+        poise::CreateReply::default()
+            .content(format!(
+                "I choose **{}**",
+                error::truncate(&options[index].0, MAX_SHOWN)
+            ))
+            .allowed_mentions(serenity::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Puts a list in a random order
+///
+/// Example: shuffle alice, bob, carol, dave
+#[poise::command(slash_command, prefix_command)]
+pub async fn shuffle(
+    ctx: Context<'_>,
+    #[description = "the items, separated by | or commas"]
+    #[rest]
+    items: String,
+) -> Result<(), Error> {
+    let mut items: Vec<_> = match parse_options(&items) {
+        Ok(options) => options.into_iter().map(|(item, _)| item).collect(),
+        Err(e) => {
+            ctx.reply(e).await?;
+            return Ok(());
+        }
+    };
+
+    ctx.data()
+        .rng
+        .with(ctx.guild_id(), |rng| items.shuffle(rng));
+    let embed = serenity::CreateEmbed::default()
+        .title("Shuffled")
+        .description(numbered(&items));
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Picks a whole number between the two given, including both of them
+///
+/// Example: random 1 100
+/// Example: random -10 10
+#[poise::command(slash_command, prefix_command)]
+pub async fn random(
+    ctx: Context<'_>,
+    #[description = "the smallest number that can be picked"] min: i64,
+    #[description = "the largest number that can be picked"] max: i64,
+) -> Result<(), Error> {
+    let (min, max) = if min <= max { (min, max) } else { (max, min) };
+
+    let num = ctx
+        .data()
+        .rng
+        .with(ctx.guild_id(), |rng| rng.gen_range(min..=max));
+    ctx.reply(format!("You got **{}**", num)).await?;
+    Ok(())
+}
+
+/// Splits everyone in your voice channel into random teams
+///
+/// The teams differ in size by at most one member, and bots are left out
+///
+/// Example: teams 2
+#[poise::command(slash_command, prefix_command, guild_only)]
+pub async fn teams(
+    ctx: Context<'_>,
+    #[description = "how many teams to make"]
+    #[min = 2]
+    #[max = 25]
+    count: usize,
+) -> Result<(), Error> {
+    // Slash commands enforce the range already, but prefix commands don't
+    if !(2..=25).contains(&count) {
+        ctx.reply("Give a number of teams between 2 and 25").await?;
+        return Ok(());
+    }
+
+    let members = {
+        let guild = ctx.guild().unwrap();
+        let channel_id = guild
+            .voice_states
+            .get(&ctx.author().id)
+            .and_then(|voice_state| voice_state.channel_id);
+        channel_id.map(|channel_id| {
+            guild
+                .voice_states
+                .values()
+                .filter(|voice_state| voice_state.channel_id == Some(channel_id))
+                .filter(|voice_state| {
+                    !guild
+                        .members
+                        .get(&voice_state.user_id)
+                        .is_some_and(|member| member.user.bot)
+                })
+                .map(|voice_state| voice_state.user_id)
+                .collect::<Vec<_>>()
+        })
+    };
+    let Some(mut members) = members else {
+        ctx.reply("You are not in a voice channel.").await?;
+        return Ok(());
+    };
+
+    if members.len() < count {
+        ctx.reply(format!(
+            "There are only {} people in your voice channel, which isn't enough for {} teams",
+            members.len(),
+            count
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    ctx.data()
+        .rng
+        .with(ctx.guild_id(), |rng| members.shuffle(rng));
+
+    // Dealing the shuffled members out in turn keeps the teams balanced
+    let mut teams = vec![Vec::new(); count];
+    for (i, member) in members.iter().enumerate() {
+        teams[i % count].push(member.mention().to_string());
+    }
+
+    let mut embed = serenity::CreateEmbed::default().title("Teams");
+    for (i, team) in teams.iter().enumerate() {
+        embed = embed.field(format!("Team {}", i + 1), team.join("\n"), true);
+    }
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Numbers the items in order, leaving out the ones that don't fit in an embed description
+fn numbered(items: &[String]) -> String {
+    // Enough room for the note about the items left out
+    let max = MAX_DESCRIPTION - 30;
+
+    let mut description = String::new();
+    let mut length = 0;
+    for (i, item) in items.iter().enumerate() {
+        let line = format!("{}. {}\n", i + 1, item);
+        length += line.chars().count();
+        if length > max {
+            description.push_str(&format!("…and {} more", items.len() - i));
+            break;
+        }
+        description.push_str(&line);
+    }
+    description
+}
+
+/// Splits a list on `|`, or on commas if there are no `|`, reading the weight after each option
+fn parse_options(input: &str) -> Result<Vec<(String, u32)>, String> {
+    let separator = if input.contains('|') { '|' } else { ',' };
+
+    let mut options = Vec::new();
+    for option in input.split(separator).map(str::trim) {
+        let weighted = option
+            .rsplit_once('*')
+            .and_then(|(name, weight)| Some((name.trim(), weight.trim().parse::<u32>().ok()?)));
+        let (name, weight) = weighted.unwrap_or((option, 1));
+        if name.is_empty() {
+            continue;
+        }
+        if weight == 0 || weight > MAX_WEIGHT {
+            return Err(format!(
+                "The weight of `{}` has to be between 1 and {}",
+                error::truncate(name, 100),
+                MAX_WEIGHT
+            ));
+        }
+        options.push((name.to_string(), weight));
+    }
+
+    if options.len() < 2 {
+        return Err("Give at least two options, separated by | or commas".to_string());
+    }
+    if options.len() > MAX_OPTIONS {
+        return Err(format!("There can't be more than {} options", MAX_OPTIONS));
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(list: &[(&str, u32)]) -> Vec<(String, u32)> {
+        list.iter()
+            .map(|&(name, weight)| (name.to_string(), weight))
+            .collect()
+    }
+
+    #[test]
+    fn splits_on_bars_before_commas() {
+        assert_eq!(
+            parse_options("a, b | c"),
+            Ok(options(&[("a, b", 1), ("c", 1)]))
+        );
+        assert_eq!(
+            parse_options(" a ,b,  c "),
+            Ok(options(&[("a", 1), ("b", 1), ("c", 1)]))
+        );
+    }
+
+    #[test]
+    fn skips_empty_options() {
+        assert_eq!(parse_options("a,, b ,"), Ok(options(&[("a", 1), ("b", 1)])));
+        assert_eq!(
+            parse_options("| a | | b"),
+            Ok(options(&[("a", 1), ("b", 1)]))
+        );
+    }
+
+    #[test]
+    fn reads_weights() {
+        assert_eq!(
+            parse_options("x * 3 | y*1000"),
+            Ok(options(&[("x", 3), ("y", 1000)]))
+        );
+        // Anything that isn't a number after the last * is part of the option
+        assert_eq!(
+            parse_options("a*b, 2*3*4"),
+            Ok(options(&[("a*b", 1), ("2*3", 4)]))
+        );
+        assert_eq!(parse_options("a*, b"), Ok(options(&[("a*", 1), ("b", 1)])));
+    }
+
+    #[test]
+    fn rejects_bad_weights() {
+        assert!(parse_options("a*0, b").unwrap_err().contains("`a`"));
+        assert!(parse_options("a, b*1001").unwrap_err().contains("`b`"));
+        // A weight with no option is dropped along with it
+        assert_eq!(
+            parse_options("a, b, *5"),
+            Ok(options(&[("a", 1), ("b", 1)]))
+        );
+    }
+
+    #[test]
+    fn needs_at_least_two_options() {
+        assert!(parse_options("").is_err());
+        assert!(parse_options("a").is_err());
+        assert!(parse_options("a*5").is_err());
+        assert!(parse_options(" , | ").is_err());
+    }
+
+    #[test]
+    fn limits_the_options() {
+        let list = |count: usize| {
+            (0..count)
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        assert_eq!(
+            parse_options(&list(MAX_OPTIONS)).unwrap().len(),
+            MAX_OPTIONS
+        );
+        assert!(parse_options(&list(MAX_OPTIONS + 1)).is_err());
+    }
+
+    #[test]
+    fn numbered_fits_in_an_embed() {
+        let items = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(numbered(&items), "1. a\n2. b\n");
+
+        let items = vec!["é".repeat(1500); 4];
+        let description = numbered(&items);
+        assert!(description.chars().count() <= MAX_DESCRIPTION);
+        assert!(description.ends_with("…and 2 more"));
+    }
+}
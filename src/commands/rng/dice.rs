@@ -0,0 +1,853 @@
+use std::fmt::{self, Write};
+
+use rand::Rng;
+
+use crate::{error, Context, Error};
+
+/// The most dice a single expression can roll, counting rerolls and explosions
+const MAX_ROLLS: usize = 1000;
+/// The most sides a die can have
+const MAX_SIDES: u32 = 1000;
+/// The longest expression that will be parsed
+const MAX_LENGTH: usize = 200;
+/// How deeply brackets and negations can be nested
+const MAX_DEPTH: usize = 20;
+/// The largest number that can be written in an expression
+const MAX_NUMBER: i64 = 1_000_000;
+/// Rolls are only listed individually when the message stays shorter than this
+const MAX_SHOWN: usize = 1800;
+
+/// Rolls dice, using the notation from tabletop games
+///
+/// `NdM` rolls N dice with M sides, and `d%` is a percentile die. After the dice you can add:
+/// `khN`/`klN` to keep the N highest or lowest, `dhN`/`dlN` to drop them,
+/// `!` to roll another die whenever one lands on its highest side, and
+/// `rN` to reroll any die showing N or less once.
+/// `adv` and `dis` roll a d20 with advantage or disadvantage.
+/// Rolls can be combined with numbers, `+ - * /` and brackets.
+///
+/// Example: dice
+/// Example: dice 2d6+3
+/// Example: dice 4d6kh3
+/// Example: dice 3d6! + 1d8r1
+/// Example: dice adv + 5
+/// Example: dice (2d10 + 4) * 2
+#[poise::command(slash_command, prefix_command, aliases("d6", "roll"))]
+pub async fn dice(
+    ctx: Context<'_>,
+    #[description = "the dice to roll, like 2d6+3 or 4d6kh3 (a single d6 if left out)"]
+    #[rest]
+    expression: Option<String>,
+) -> Result<(), Error> {
+    let expression = expression.unwrap_or_else(|| "1d6".to_string());
+
+    let roll = parse(&expression)
+        .and_then(|expr| ctx.data().rng.with(ctx.guild_id(), |rng| roll(&expr, rng)));
+    let msg = match roll {
+        Ok(roll) => roll.to_string(),
+        Err(e) => {
+            // Only the start is repeated, as the whole expression can be too long to send back
+            let expression = expression.trim();
+            let shown = error::truncate(expression, MAX_LENGTH);
+            let cut = if shown.len() < expression.len() {
+                "…"
+            } else {
+                ""
+            };
+            format!("Couldn't roll `{}{}`: {}", shown, cut, e)
+        }
+    };
+    ctx.reply(msg).await?;
+    Ok(())
+}
+
+/// Why an expression couldn't be parsed or rolled
+#[derive(Debug, PartialEq)]
+pub enum DiceError {
+    Empty,
+    TooLong,
+    TooDeep,
+    Unexpected(char),
+    UnexpectedEnd,
+    NumberTooLarge,
+    NoDice,
+    TooManySides,
+    KeepTooMany { keep: u32, count: u32 },
+    RerollEverything,
+    ExplodeOneSided,
+    TooManyRolls,
+    DivideByZero,
+    Overflow,
+}
+
+impl fmt::Display for DiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiceError::Empty => write!(f, "there's nothing to roll"),
+            DiceError::TooLong => write!(f, "it's longer than {} characters", MAX_LENGTH),
+            DiceError::TooDeep => write!(f, "it's nested more than {} levels deep", MAX_DEPTH),
+            DiceError::Unexpected(c) => write!(f, "`{}` wasn't expected there", c),
+            DiceError::UnexpectedEnd => write!(f, "it ends too soon"),
+            DiceError::NumberTooLarge => write!(f, "numbers can't be larger than {}", MAX_NUMBER),
+            DiceError::NoDice => write!(f, "at least one die has to be rolled"),
+            DiceError::TooManySides => write!(f, "dice can't have more than {} sides", MAX_SIDES),
+            DiceError::KeepTooMany { keep, count } => {
+                write!(f, "can't keep or drop {} of only {} dice", keep, count)
+            }
+            DiceError::RerollEverything => write!(f, "that would reroll every side of the die"),
+            DiceError::ExplodeOneSided => write!(f, "one sided dice can't explode"),
+            DiceError::TooManyRolls => write!(f, "it rolls more than {} dice", MAX_ROLLS),
+            DiceError::DivideByZero => write!(f, "it divides by zero"),
+            DiceError::Overflow => write!(f, "the result is too large"),
+        }
+    }
+}
+
+impl std::error::Error for DiceError {}
+
+/// A parsed dice expression
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(i64),
+    Dice(Dice),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// A group of identical dice, along with what's done to them after they're rolled
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dice {
+    pub count: u32,
+    pub sides: u32,
+    /// Dice showing this or less are rerolled once
+    pub reroll: Option<u32>,
+    /// Whether a die landing on its highest side adds another die
+    pub explode: bool,
+    pub keep: Option<Keep>,
+}
+
+/// Which of the dice count towards the total
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Keep {
+    Highest(u32),
+    Lowest(u32),
+    DropHighest(u32),
+    DropLowest(u32),
+}
+
+impl fmt::Display for Dice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+        if let Some(reroll) = self.reroll {
+            write!(f, "r{}", reroll)?;
+        }
+        if self.explode {
+            write!(f, "!")?;
+        }
+        match self.keep {
+            Some(Keep::Highest(n)) => write!(f, "kh{}", n),
+            Some(Keep::Lowest(n)) => write!(f, "kl{}", n),
+            Some(Keep::DropHighest(n)) => write!(f, "dh{}", n),
+            Some(Keep::DropLowest(n)) => write!(f, "dl{}", n),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The outcome of rolling an expression
+#[derive(Debug)]
+pub struct Roll {
+    pub total: i64,
+    /// Every group of dice in the expression, in order
+    pub dice: Vec<DiceRoll>,
+}
+
+/// The outcome of rolling one group of dice
+#[derive(Debug)]
+pub struct DiceRoll {
+    pub dice: Dice,
+    pub rolls: Vec<Die>,
+    pub total: i64,
+}
+
+/// A single die that was rolled
+#[derive(Clone, Copy, Debug)]
+pub struct Die {
+    pub value: u32,
+    /// Whether the die was rerolled or not kept, so it doesn't count towards the total
+    pub dropped: bool,
+}
+
+impl fmt::Display for Roll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut details = String::new();
+        for roll in &self.dice {
+            let rolls: Vec<_> = roll
+                .rolls
+                .iter()
+                .map(|die| {
+                    if die.dropped {
+                        format!("~~{}~~", die.value)
+                    } else {
+                        die.value.to_string()
+                    }
+                })
+                .collect();
+            writeln!(
+                details,
+                "`{}` [{}] = {}",
+                roll.dice,
+                rolls.join(", "),
+                roll.total
+            )?;
+        }
+
+        // A big roll would go over the message limit, so only the total is shown
+        if details.len() <= MAX_SHOWN && !self.dice.is_empty() {
+            f.write_str(&details)?;
+        }
+        write!(f, "**Total: {}**", self.total)
+    }
+}
+
+/// Parses a dice expression, checking it against the limits
+pub fn parse(input: &str) -> Result<Expr, DiceError> {
+    if input.len() > MAX_LENGTH {
+        return Err(DiceError::TooLong);
+    }
+    let chars: Vec<char> = input
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if chars.is_empty() {
+        return Err(DiceError::Empty);
+    }
+
+    let mut parser = Parser {
+        chars: &chars,
+        pos: 0,
+        depth: 0,
+        dice: 0,
+    };
+    let expr = parser.expr()?;
+    match parser.peek() {
+        Some(c) => Err(DiceError::Unexpected(c)),
+        None => Ok(expr),
+    }
+}
+
+struct Parser<'a> {
+    chars: &'a [char],
+    pos: usize,
+    depth: usize,
+    /// How many dice the expression rolls before any rerolls or explosions
+    dice: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let len = word.chars().count();
+        let matches = self
+            .chars
+            .get(self.pos..self.pos + len)
+            .is_some_and(|chars| chars.iter().copied().eq(word.chars()));
+        if matches {
+            self.pos += len;
+        }
+        matches
+    }
+
+    fn expr(&mut self) -> Result<Expr, DiceError> {
+        let mut expr = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                Op::Add
+            } else if self.eat('-') {
+                Op::Sub
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, DiceError> {
+        let mut expr = self.factor()?;
+        loop {
+            let op = if self.eat('*') || self.eat('x') {
+                Op::Mul
+            } else if self.eat('/') {
+                Op::Div
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.factor()?));
+        }
+    }
+
+    fn factor(&mut self) -> Result<Expr, DiceError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(DiceError::TooDeep);
+        }
+        let expr = self.atom();
+        self.depth -= 1;
+        expr
+    }
+
+    fn atom(&mut self) -> Result<Expr, DiceError> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.factor()?)));
+        }
+        if self.eat('(') {
+            let expr = self.expr()?;
+            if !self.eat(')') {
+                return Err(self
+                    .peek()
+                    .map_or(DiceError::UnexpectedEnd, DiceError::Unexpected));
+            }
+            return Ok(expr);
+        }
+        if self.eat_word("adv") {
+            return self.dice(2, 20, Some(Keep::Highest(1)));
+        }
+        if self.eat_word("dis") {
+            return self.dice(2, 20, Some(Keep::Lowest(1)));
+        }
+
+        let number = self.number()?;
+        if !self.eat('d') {
+            return match number {
+                Some(n) => Ok(Expr::Number(n)),
+                None => Err(self
+                    .peek()
+                    .map_or(DiceError::UnexpectedEnd, DiceError::Unexpected)),
+            };
+        }
+
+        let count = number.unwrap_or(1);
+        let sides = if self.eat('%') {
+            100
+        } else {
+            self.number()?.ok_or_else(|| {
+                self.peek()
+                    .map_or(DiceError::UnexpectedEnd, DiceError::Unexpected)
+            })?
+        };
+        self.dice(count, sides, None)
+    }
+
+    /// Reads the modifiers after a group of dice and checks that it can be rolled
+    fn dice(&mut self, count: i64, sides: i64, keep: Option<Keep>) -> Result<Expr, DiceError> {
+        if count < 1 || sides < 1 {
+            return Err(DiceError::NoDice);
+        }
+        if sides > MAX_SIDES as i64 {
+            return Err(DiceError::TooManySides);
+        }
+        if count > MAX_ROLLS as i64 {
+            return Err(DiceError::TooManyRolls);
+        }
+        let mut dice = Dice {
+            count: count as u32,
+            sides: sides as u32,
+            reroll: None,
+            explode: false,
+            keep,
+        };
+
+        loop {
+            if self.eat('!') {
+                dice.explode = true;
+            } else if self.eat('r') {
+                dice.reroll = Some(self.modifier_number()?);
+            } else if self.eat_word("kl") {
+                dice.keep = Some(Keep::Lowest(self.modifier_number()?));
+            } else if self.eat_word("kh") || self.eat('k') {
+                dice.keep = Some(Keep::Highest(self.modifier_number()?));
+            } else if self.eat_word("dh") {
+                dice.keep = Some(Keep::DropHighest(self.modifier_number()?));
+            } else if self.eat_word("dl") {
+                dice.keep = Some(Keep::DropLowest(self.modifier_number()?));
+            } else {
+                break;
+            }
+        }
+
+        if dice.reroll.is_some_and(|r| r >= dice.sides) {
+            return Err(DiceError::RerollEverything);
+        }
+        if dice.explode && dice.sides == 1 {
+            return Err(DiceError::ExplodeOneSided);
+        }
+        if let Some(
+            Keep::Highest(n) | Keep::Lowest(n) | Keep::DropHighest(n) | Keep::DropLowest(n),
+        ) = dice.keep
+        {
+            if n > dice.count {
+                return Err(DiceError::KeepTooMany {
+                    keep: n,
+                    count: dice.count,
+                });
+            }
+        }
+
+        self.dice += dice.count as usize;
+        if self.dice > MAX_ROLLS {
+            return Err(DiceError::TooManyRolls);
+        }
+        Ok(Expr::Dice(dice))
+    }
+
+    /// The number after a modifier, which is 1 when it's left out
+    fn modifier_number(&mut self) -> Result<u32, DiceError> {
+        Ok(self.number()?.unwrap_or(1) as u32)
+    }
+
+    fn number(&mut self) -> Result<Option<i64>, DiceError> {
+        let start = self.pos;
+        let mut n: i64 = 0;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+            n = n * 10 + digit as i64;
+            if n > MAX_NUMBER {
+                return Err(DiceError::NumberTooLarge);
+            }
+            self.pos += 1;
+        }
+        Ok((self.pos > start).then_some(n))
+    }
+}
+
+/// Rolls every group of dice in the expression and works out the total
+pub fn roll(expr: &Expr, rng: &mut impl Rng) -> Result<Roll, DiceError> {
+    let mut dice = Vec::new();
+    let mut rolled = 0;
+    let total = evaluate(expr, rng, &mut dice, &mut rolled)?;
+    Ok(Roll { total, dice })
+}
+
+fn evaluate(
+    expr: &Expr,
+    rng: &mut impl Rng,
+    dice: &mut Vec<DiceRoll>,
+    rolled: &mut usize,
+) -> Result<i64, DiceError> {
+    match expr {
+        Expr::Number(n) => Ok(*n),
+        Expr::Neg(expr) => evaluate(expr, rng, dice, rolled)?
+            .checked_neg()
+            .ok_or(DiceError::Overflow),
+        Expr::Binary(op, left, right) => {
+            let left = evaluate(left, rng, dice, rolled)?;
+            let right = evaluate(right, rng, dice, rolled)?;
+            match op {
+                Op::Add => left.checked_add(right).ok_or(DiceError::Overflow),
+                Op::Sub => left.checked_sub(right).ok_or(DiceError::Overflow),
+                Op::Mul => left.checked_mul(right).ok_or(DiceError::Overflow),
+                Op::Div if right == 0 => Err(DiceError::DivideByZero),
+                Op::Div => left.checked_div(right).ok_or(DiceError::Overflow),
+            }
+        }
+        Expr::Dice(group) => {
+            let roll = roll_dice(group, rng, rolled)?;
+            let total = roll.total;
+            dice.push(roll);
+            Ok(total)
+        }
+    }
+}
+
+fn roll_dice(dice: &Dice, rng: &mut impl Rng, rolled: &mut usize) -> Result<DiceRoll, DiceError> {
+    let mut roll_one = || {
+        *rolled += 1;
+        if *rolled > MAX_ROLLS {
+            return Err(DiceError::TooManyRolls);
+        }
+        Ok(rng.gen_range(1..=dice.sides))
+    };
+
+    let mut rolls = Vec::new();
+    let mut pending = dice.count;
+    while pending > 0 {
+        pending -= 1;
+        let mut value = roll_one()?;
+
+        if dice.reroll.is_some_and(|r| value <= r) {
+            rolls.push(Die {
+                value,
+                dropped: true,
+            });
+            value = roll_one()?;
+        }
+        rolls.push(Die {
+            value,
+            dropped: false,
+        });
+
+        // Each explosion is a new die, which can explode again
+        if dice.explode && value == dice.sides {
+            pending += 1;
+        }
+    }
+
+    if let Some(keep) = dice.keep {
+        let mut kept: Vec<usize> = (0..rolls.len()).filter(|&i| !rolls[i].dropped).collect();
+        kept.sort_by_key(|&i| rolls[i].value);
+        let dropped = match keep {
+            Keep::Highest(n) => &kept[..kept.len().saturating_sub(n as usize)],
+            Keep::Lowest(n) => &kept[(n as usize).min(kept.len())..],
+            Keep::DropHighest(n) => &kept[kept.len().saturating_sub(n as usize)..],
+            Keep::DropLowest(n) => &kept[..(n as usize).min(kept.len())],
+        };
+        for &i in dropped {
+            rolls[i].dropped = true;
+        }
+    }
+
+    let total = rolls
+        .iter()
+        .filter(|die| !die.dropped)
+        .map(|die| die.value as i64)
+        .sum();
+    Ok(DiceRoll {
+        dice: dice.clone(),
+        rolls,
+        total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn roll_str(input: &str, seed: u64) -> Result<Roll, DiceError> {
+        parse(input).and_then(|expr| roll(&expr, &mut StdRng::seed_from_u64(seed)))
+    }
+
+    fn total(input: &str) -> i64 {
+        roll_str(input, 0).unwrap().total
+    }
+
+    fn dice(count: u32, sides: u32) -> Dice {
+        Dice {
+            count,
+            sides,
+            reroll: None,
+            explode: false,
+            keep: None,
+        }
+    }
+
+    /// Rolls a single group of dice with many seeds, handing each roll to `check`
+    fn for_each_roll(input: &str, check: impl Fn(&DiceRoll)) {
+        for seed in 0..500 {
+            let roll = roll_str(input, seed).unwrap();
+            assert_eq!(roll.dice.len(), 1);
+            check(&roll.dice[0]);
+        }
+    }
+
+    fn kept(roll: &DiceRoll) -> Vec<u32> {
+        roll.rolls
+            .iter()
+            .filter(|die| !die.dropped)
+            .map(|die| die.value)
+            .collect()
+    }
+
+    fn dropped(roll: &DiceRoll) -> Vec<u32> {
+        roll.rolls
+            .iter()
+            .filter(|die| die.dropped)
+            .map(|die| die.value)
+            .collect()
+    }
+
+    #[test]
+    fn parses_dice() {
+        assert_eq!(parse("2d6"), Ok(Expr::Dice(dice(2, 6))));
+        assert_eq!(parse("d20"), Ok(Expr::Dice(dice(1, 20))));
+        assert_eq!(parse("D%"), Ok(Expr::Dice(dice(1, 100))));
+        assert_eq!(parse(" 3 d 8 "), Ok(Expr::Dice(dice(3, 8))));
+        assert_eq!(
+            parse("4d6r1!kh3"),
+            Ok(Expr::Dice(Dice {
+                reroll: Some(1),
+                explode: true,
+                keep: Some(Keep::Highest(3)),
+                ..dice(4, 6)
+            }))
+        );
+        assert_eq!(
+            parse("4d6k"),
+            Ok(Expr::Dice(Dice {
+                keep: Some(Keep::Highest(1)),
+                ..dice(4, 6)
+            }))
+        );
+    }
+
+    #[test]
+    fn rolls_within_range() {
+        for_each_roll("3d6", |roll| {
+            assert_eq!(roll.rolls.len(), 3);
+            assert!(roll.rolls.iter().all(|die| (1..=6).contains(&die.value)));
+            assert!((3..=18).contains(&roll.total));
+            assert_eq!(roll.total, kept(roll).iter().sum::<u32>() as i64);
+        });
+    }
+
+    #[test]
+    fn keeps_highest() {
+        for_each_roll("4d6kh3", |roll| {
+            let (kept, dropped) = (kept(roll), dropped(roll));
+            assert_eq!((kept.len(), dropped.len()), (3, 1));
+            assert!(kept.iter().all(|&k| k >= dropped[0]));
+            assert_eq!(roll.total, kept.iter().sum::<u32>() as i64);
+        });
+    }
+
+    #[test]
+    fn keeps_lowest() {
+        for_each_roll("4d6kl1", |roll| {
+            let (kept, dropped) = (kept(roll), dropped(roll));
+            assert_eq!((kept.len(), dropped.len()), (1, 3));
+            assert!(dropped.iter().all(|&d| d >= kept[0]));
+        });
+    }
+
+    #[test]
+    fn drops_highest() {
+        for_each_roll("4d6dh1", |roll| {
+            let (kept, dropped) = (kept(roll), dropped(roll));
+            assert_eq!((kept.len(), dropped.len()), (3, 1));
+            assert!(kept.iter().all(|&k| k <= dropped[0]));
+        });
+    }
+
+    #[test]
+    fn drops_lowest() {
+        for_each_roll("4d6dl2", |roll| {
+            let (kept, dropped) = (kept(roll), dropped(roll));
+            assert_eq!((kept.len(), dropped.len()), (2, 2));
+            assert!(kept.iter().all(|&k| dropped.iter().all(|&d| k >= d)));
+        });
+    }
+
+    #[test]
+    fn explodes_on_the_highest_side() {
+        for_each_roll("5d2!", |roll| {
+            let twos = roll.rolls.iter().filter(|die| die.value == 2).count();
+            // Every 2 adds another die, and the last die can't be a 2
+            assert_eq!(roll.rolls.len(), 5 + twos);
+            assert_eq!(roll.rolls.last().unwrap().value, 1);
+        });
+    }
+
+    #[test]
+    fn rerolls_once() {
+        for_each_roll("1d4r2", |roll| match roll.rolls.as_slice() {
+            [die] => assert!(!die.dropped && die.value > 2),
+            [first, second] => {
+                assert!(first.dropped && first.value <= 2);
+                assert!(!second.dropped);
+                assert_eq!(roll.total, second.value as i64);
+            }
+            rolls => panic!("rolled {} dice", rolls.len()),
+        });
+    }
+
+    #[test]
+    fn rolls_with_advantage_and_disadvantage() {
+        assert_eq!(
+            parse("adv"),
+            Ok(Expr::Dice(Dice {
+                keep: Some(Keep::Highest(1)),
+                ..dice(2, 20)
+            }))
+        );
+        for_each_roll("adv", |roll| {
+            let max = roll.rolls.iter().map(|die| die.value).max().unwrap();
+            assert_eq!(roll.total, max as i64);
+        });
+        for_each_roll("dis", |roll| {
+            let min = roll.rolls.iter().map(|die| die.value).min().unwrap();
+            assert_eq!(roll.total, min as i64);
+        });
+    }
+
+    #[test]
+    fn follows_precedence() {
+        assert_eq!(total("3 + 4 * 2"), 11);
+        assert_eq!(total("(3 + 4) * 2"), 14);
+        assert_eq!(total("3x4"), 12);
+        assert_eq!(total("10 - 2 - 3"), 5);
+        assert_eq!(total("16 / 2 / 2"), 4);
+        assert_eq!(total("7 / 2"), 3);
+        assert_eq!(total("-2 * 3"), -6);
+        assert_eq!(total("2 * -3"), -6);
+        assert_eq!(total("--4"), 4);
+        assert_eq!(total("((1 + 2) * (3 + 4)) - 1"), 20);
+    }
+
+    #[test]
+    fn combines_dice_with_arithmetic() {
+        for seed in 0..200 {
+            let roll = roll_str("2d6 * 10 + 1d1", seed).unwrap();
+            assert_eq!(roll.dice.len(), 2);
+            assert_eq!(roll.total, roll.dice[0].total * 10 + 1);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_syntax() {
+        assert_eq!(parse("").unwrap_err(), DiceError::Empty);
+        assert_eq!(parse("   ").unwrap_err(), DiceError::Empty);
+        assert_eq!(parse("2d6)").unwrap_err(), DiceError::Unexpected(')'));
+        assert_eq!(parse("2d6?").unwrap_err(), DiceError::Unexpected('?'));
+        assert_eq!(parse("(1)2").unwrap_err(), DiceError::Unexpected('2'));
+        assert_eq!(parse("2d").unwrap_err(), DiceError::UnexpectedEnd);
+        assert_eq!(parse("1 +").unwrap_err(), DiceError::UnexpectedEnd);
+        assert_eq!(parse("(1").unwrap_err(), DiceError::UnexpectedEnd);
+    }
+
+    #[test]
+    fn rejects_impossible_dice() {
+        assert_eq!(parse("0d6").unwrap_err(), DiceError::NoDice);
+        assert_eq!(parse("2d0").unwrap_err(), DiceError::NoDice);
+        assert_eq!(
+            parse("2d6kh3").unwrap_err(),
+            DiceError::KeepTooMany { keep: 3, count: 2 }
+        );
+        assert_eq!(
+            parse("2d6dl3").unwrap_err(),
+            DiceError::KeepTooMany { keep: 3, count: 2 }
+        );
+        assert_eq!(parse("1d6r6").unwrap_err(), DiceError::RerollEverything);
+        assert_eq!(parse("1d1!").unwrap_err(), DiceError::ExplodeOneSided);
+    }
+
+    #[test]
+    fn enforces_limits() {
+        assert!(parse(&"1".repeat(MAX_LENGTH)).is_err());
+        assert_eq!(
+            parse(&"1+".repeat(MAX_LENGTH / 2 + 1)).unwrap_err(),
+            DiceError::TooLong
+        );
+
+        let nested = |depth| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(total(&nested(MAX_DEPTH - 1)), 1);
+        assert_eq!(parse(&nested(MAX_DEPTH)).unwrap_err(), DiceError::TooDeep);
+        assert_eq!(
+            parse(&format!("{}1", "-".repeat(MAX_DEPTH))).unwrap_err(),
+            DiceError::TooDeep
+        );
+
+        assert_eq!(total(&MAX_NUMBER.to_string()), MAX_NUMBER);
+        assert_eq!(
+            parse(&(MAX_NUMBER + 1).to_string()).unwrap_err(),
+            DiceError::NumberTooLarge
+        );
+
+        assert!(parse(&format!("1d{}", MAX_SIDES)).is_ok());
+        assert_eq!(
+            parse(&format!("1d{}", MAX_SIDES + 1)).unwrap_err(),
+            DiceError::TooManySides
+        );
+
+        assert!(parse(&format!("{}d6", MAX_ROLLS)).is_ok());
+        assert_eq!(
+            parse(&format!("{}d6", MAX_ROLLS + 1)).unwrap_err(),
+            DiceError::TooManyRolls
+        );
+        assert_eq!(parse("600d6 + 600d6").unwrap_err(), DiceError::TooManyRolls);
+    }
+
+    #[test]
+    fn limits_rerolls_and_explosions() {
+        // Half of these dice explode, going well over the limit
+        assert_eq!(
+            roll_str(&format!("{}d2!", MAX_ROLLS), 0).unwrap_err(),
+            DiceError::TooManyRolls
+        );
+        assert_eq!(
+            roll_str(&format!("{}d2r1", MAX_ROLLS), 0).unwrap_err(),
+            DiceError::TooManyRolls
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arithmetic() {
+        assert_eq!(roll_str("1 / 0", 0).unwrap_err(), DiceError::DivideByZero);
+        assert_eq!(
+            roll_str("1d6 / (2 - 2)", 0).unwrap_err(),
+            DiceError::DivideByZero
+        );
+        assert_eq!(
+            roll_str("1000000 * 1000000 * 1000000 * 1000000", 0).unwrap_err(),
+            DiceError::Overflow
+        );
+        assert_eq!(
+            roll_str("0 - 1000000 * 1000000 * 1000000 * 1000000", 0).unwrap_err(),
+            DiceError::Overflow
+        );
+    }
+
+    #[test]
+    fn shows_rolls_and_total() {
+        let roll = roll_str("4d6dl1 + 2", 0).unwrap();
+        let shown = roll.to_string();
+        assert!(shown.starts_with("`4d6dl1` ["));
+        assert!(shown.contains("~~"));
+        assert!(shown.ends_with(&format!("**Total: {}**", roll.total)));
+
+        // Too many rolls to list only shows the total
+        let roll = roll_str("1000d1000", 0).unwrap();
+        assert_eq!(roll.to_string(), format!("**Total: {}**", roll.total));
+    }
+
+    #[test]
+    fn never_panics_on_random_input() {
+        let alphabet: Vec<char> = "0123456789d%khlr!+-*/x()adviseD ?".chars().collect();
+        let mut rng = StdRng::seed_from_u64(697);
+
+        for _ in 0..100_000 {
+            let len = rng.gen_range(0..40);
+            let input: String = (0..len)
+                .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+                .collect();
+
+            if let Ok(expr) = parse(&input) {
+                if let Ok(roll) = roll(&expr, &mut rng) {
+                    let dice: i64 = roll.dice.iter().map(|d| d.rolls.len() as i64).sum();
+                    assert!(dice <= MAX_ROLLS as i64, "{:?} rolled {} dice", input, dice);
+                    let _ = roll.to_string();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{Context, Data, Error};
+use rand::Rng;
+
+pub mod ball8;
+mod choice;
+mod dice;
+pub mod provider;
+mod verify;
+
+/// The commands in this module
+pub fn commands() -> Vec<poise::Command<Data, Error>> {
+    vec![
+        coinflip(),
+        dice::dice(),
+        ball8::ball8(),
+        #[cfg(feature = "database")]
+        ball8::ball8_answers(),
+        choice::choose(),
+        choice::shuffle(),
+        choice::random(),
+        choice::teams(),
+        verify::verify(),
+    ]
+}
+
+/// Flip a coin
+///
+/// Simulates flipping a coin, landing on either heads or tails
+///
+/// Example: coinflip
+#[poise::command(slash_command, prefix_command, aliases("cf"))]
+pub async fn coinflip(ctx: Context<'_>) -> Result<(), Error> {
+    let res: bool = ctx.data().rng.with(ctx.guild_id(), |rng| rng.gen());
+
+    if res {
+        ctx.reply("The coin landed on **heads**.").await?;
+    } else {
+        ctx.reply("The coin landed on **tails**.").await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,298 @@
+use std::{collections::HashMap, fmt::Write, sync::Mutex};
+
+use poise::serenity_prelude as serenity;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "database")]
+use crate::db;
+use crate::Error;
+
+/// The generator behind every random command
+///
+/// ChaCha20 gives the same numbers for a seed on every platform and version, so a revealed seed can be
+/// checked by anyone
+pub type CommandRng = ChaCha20Rng;
+
+/// Hands out the random numbers for the rng commands
+///
+/// Guilds in verify mode get numbers from their own committed seed, everywhere else shares one generator
+pub struct RngProvider {
+    rng: Mutex<CommandRng>,
+    commitments: Mutex<HashMap<serenity::GuildId, Commitment>>,
+    /// Whether commitments are saved to the database, which is turned on by restoring them from it
+    #[cfg(feature = "database")]
+    persist: bool,
+}
+
+/// A seed whose hash has been published, and the generator it was used to start
+pub struct Commitment {
+    seed: [u8; 32],
+    rng: CommandRng,
+    /// How many commands have used the seed
+    pub uses: u64,
+}
+
+impl Commitment {
+    fn new() -> Self {
+        let mut seed = [0; 32];
+        rand::thread_rng().fill_bytes(&mut seed);
+        Commitment {
+            seed,
+            rng: CommandRng::from_seed(seed),
+            uses: 0,
+        }
+    }
+
+    /// The SHA-256 hash of the seed, which can be published without giving the seed away
+    pub fn hash(&self) -> String {
+        to_hex(&Sha256::digest(self.seed))
+    }
+
+    pub fn seed(&self) -> String {
+        to_hex(&self.seed)
+    }
+}
+
+impl RngProvider {
+    /// A provider seeded from the OS, for real randomness
+    pub fn from_entropy() -> Self {
+        Self::new(CommandRng::from_entropy())
+    }
+
+    /// A provider that gives the same numbers every time, so results can be reproduced
+    pub fn from_seed(seed: u64) -> Self {
+        Self::new(CommandRng::seed_from_u64(seed))
+    }
+
+    fn new(rng: CommandRng) -> Self {
+        RngProvider {
+            rng: Mutex::new(rng),
+            commitments: Mutex::new(HashMap::new()),
+            #[cfg(feature = "database")]
+            persist: false,
+        }
+    }
+
+    /// Loads the commitments saved before a restart, and saves any made from now on
+    #[cfg(feature = "database")]
+    pub async fn restore(mut self) -> Result<Self, sqlx::Error> {
+        let saved = db::rng_commitments::all().await?;
+        let commitments = self.commitments.get_mut().unwrap();
+        for saved in saved {
+            let mut rng = CommandRng::from_seed(saved.seed);
+            rng.set_word_pos(saved.word_pos);
+            commitments.insert(
+                saved.guild_id,
+                Commitment {
+                    seed: saved.seed,
+                    rng,
+                    uses: saved.uses,
+                },
+            );
+        }
+        self.persist = true;
+        Ok(self)
+    }
+
+    /// Runs `f` with the generator for the guild, which is its committed one when in verify mode
+    pub fn with<T>(
+        &self,
+        guild_id: Option<serenity::GuildId>,
+        f: impl FnOnce(&mut CommandRng) -> T,
+    ) -> T {
+        if let Some(guild_id) = guild_id {
+            let mut commitments = self.commitments.lock().unwrap();
+            if let Some(commitment) = commitments.get_mut(&guild_id) {
+                commitment.uses += 1;
+                let result = f(&mut commitment.rng);
+                #[cfg(feature = "database")]
+                if self.persist {
+                    let saved = db::rng_commitments::SavedCommitment {
+                        guild_id,
+                        seed: commitment.seed,
+                        uses: commitment.uses,
+                        word_pos: commitment.rng.get_word_pos(),
+                    };
+                    tokio::spawn(async move {
+                        if let Err(e) = db::rng_commitments::update(&saved).await {
+                            tracing::warn!(
+                                "failed to save the rng commitment of {}: {}",
+                                guild_id,
+                                e
+                            );
+                        }
+                    });
+                }
+                return result;
+            }
+        }
+        f(&mut self.rng.lock().unwrap())
+    }
+
+    /// Commits the guild to a new seed and returns its hash, or `None` if it already has one
+    pub async fn commit(&self, guild_id: serenity::GuildId) -> Result<Option<String>, Error> {
+        let commitment = Commitment::new();
+        let hash = commitment.hash();
+        #[cfg(feature = "database")]
+        let seed = commitment.seed;
+        {
+            let mut commitments = self.commitments.lock().unwrap();
+            if commitments.contains_key(&guild_id) {
+                return Ok(None);
+            }
+            commitments.insert(guild_id, commitment);
+        }
+
+        // Saved before the hash is published, since a seed lost to a restart could never be revealed
+        #[cfg(feature = "database")]
+        if self.persist {
+            if let Err(e) = db::rng_commitments::insert(guild_id, &seed).await {
+                self.commitments.lock().unwrap().remove(&guild_id);
+                return Err(e.into());
+            }
+        }
+        Ok(Some(hash))
+    }
+
+    /// Ends verify mode for the guild, returning the commitment so its seed can be published
+    pub async fn reveal(&self, guild_id: serenity::GuildId) -> Result<Option<Commitment>, Error> {
+        let commitment = self.commitments.lock().unwrap().remove(&guild_id);
+        #[cfg(feature = "database")]
+        if self.persist && commitment.is_some() {
+            db::rng_commitments::delete(guild_id).await?;
+        }
+        Ok(commitment)
+    }
+
+    /// The hash and uses of the guild's commitment, if it's in verify mode
+    pub fn status(&self, guild_id: serenity::GuildId) -> Option<(String, u64)> {
+        self.commitments
+            .lock()
+            .unwrap()
+            .get(&guild_id)
+            .map(|c| (c.hash(), c.uses))
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+
+    use super::super::{ball8, dice};
+    use super::*;
+
+    const DRAWS: usize = 60_000;
+
+    fn guild() -> serenity::GuildId {
+        serenity::GuildId::new(697)
+    }
+
+    /// Asserts that `count` out of `DRAWS` is within 5% of the expected share
+    fn assert_share(count: usize, expected: f64) {
+        let share = count as f64 / DRAWS as f64;
+        assert!(
+            (share - expected).abs() < expected * 0.05,
+            "got {share}, expected {expected}"
+        );
+    }
+
+    #[test]
+    fn same_seed_gives_same_numbers() {
+        let numbers = |seed| {
+            let provider = RngProvider::from_seed(seed);
+            (0..20)
+                .map(|_| provider.with(None, |rng| rng.gen::<u64>()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(numbers(1), numbers(1));
+        assert_ne!(numbers(1), numbers(2));
+    }
+
+    #[test]
+    fn coinflip_is_fair() {
+        let provider = RngProvider::from_seed(1);
+        let heads = (0..DRAWS)
+            .filter(|_| provider.with(None, |rng| rng.gen::<bool>()))
+            .count();
+        assert_share(heads, 0.5);
+    }
+
+    #[test]
+    fn dice_are_uniform() {
+        let provider = RngProvider::from_seed(2);
+        let expr = dice::parse("1d6").unwrap();
+        let mut faces = [0; 6];
+        for _ in 0..DRAWS {
+            let roll = provider.with(None, |rng| dice::roll(&expr, rng)).unwrap();
+            faces[roll.total as usize - 1] += 1;
+        }
+        for count in faces {
+            assert_share(count, 1.0 / 6.0);
+        }
+    }
+
+    #[test]
+    fn ball8_follows_the_weights() {
+        let provider = RngProvider::from_seed(3);
+        let answers: Vec<_> = ball8::builtin_answers().collect();
+        let weights = WeightedIndex::new(answers.iter().map(|answer| answer.weight)).unwrap();
+        let total: u32 = answers.iter().map(|answer| answer.weight).sum();
+
+        let mut counts = vec![0; answers.len()];
+        for _ in 0..DRAWS {
+            counts[provider.with(None, |rng| weights.sample(rng))] += 1;
+        }
+        for (answer, count) in answers.iter().zip(counts) {
+            assert_share(count, answer.weight as f64 / total as f64);
+        }
+
+        let negative: u32 = answers
+            .iter()
+            .filter(|answer| answer.kind == ball8::Kind::Negative)
+            .map(|answer| answer.weight)
+            .sum();
+        assert_eq!(negative * 5, total * 2);
+    }
+
+    #[tokio::test]
+    async fn revealed_seed_matches_the_commitment() {
+        let provider = RngProvider::from_seed(4);
+        let hash = provider.commit(guild()).await.unwrap().unwrap();
+        assert!(provider.commit(guild()).await.unwrap().is_none());
+        assert_eq!(provider.status(guild()), Some((hash.clone(), 0)));
+
+        let numbers: Vec<u64> = (0..5)
+            .map(|_| provider.with(Some(guild()), |rng| rng.gen()))
+            .collect();
+        // Other guilds and DMs keep using the shared generator
+        provider.with(Some(serenity::GuildId::new(1)), |rng| rng.gen::<u64>());
+        provider.with(None, |rng| rng.gen::<u64>());
+
+        let commitment = provider.reveal(guild()).await.unwrap().unwrap();
+        assert_eq!(commitment.uses, 5);
+        assert_eq!(commitment.hash(), hash);
+        assert!(provider.status(guild()).is_none());
+        assert!(provider.reveal(guild()).await.unwrap().is_none());
+
+        let seed: [u8; 32] = (0..64)
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&commitment.seed()[i..i + 2], 16).unwrap())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        assert_eq!(to_hex(&Sha256::digest(seed)), hash);
+
+        let mut replay = CommandRng::from_seed(seed);
+        let replayed: Vec<u64> = (0..5).map(|_| replay.gen()).collect();
+        assert_eq!(numbers, replayed);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{Context, Error};
+
+/// Proves the random commands in this server aren't rigged, e.g. for a giveaway
+///
+/// `verify start` publishes the hash of a secret seed, which the random commands in this server then use.
+/// `verify reveal` publishes the seed, so anyone can check it matches the hash and replay the results.
+///
+/// Example: verify start
+/// Example: verify reveal
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("start", "reveal", "status")
+)]
+pub async fn verify(ctx: Context<'_>) -> Result<(), Error> {
+    status_inner(ctx).await
+}
+
+/// Commits to a secret seed for the random commands in this server, publishing its hash
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn start(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+
+    let msg = match ctx.data().rng.commit(guild_id).await? {
+        Some(hash) => format!(
+            "Verify mode is on. The SHA-256 hash of the seed is `{}`\n\
+             `coinflip`, `dice`, `8ball`, `choose`, `shuffle`, `random` and `teams` in this server \
+             use the seed until `verify reveal`",
+            hash
+        ),
+        None => "Verify mode is already on, use `verify reveal` to end it first".to_string(),
+    };
+    ctx.reply(msg).await?;
+    Ok(())
+}
+
+/// Publishes the seed and ends verify mode
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn reveal(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+
+    let msg = match ctx.data().rng.reveal(guild_id).await? {
+        Some(commitment) => format!(
+            "The seed was `{}`, with the SHA-256 hash `{}`, and was used by {} commands\n\
+             Seeding ChaCha20 with it gives the same results, in the same order",
+            commitment.seed(),
+            commitment.hash(),
+            commitment.uses
+        ),
+        None => "Verify mode is not on.".to_string(),
+    };
+    ctx.reply(msg).await?;
+    Ok(())
+}
+
+/// Shows the hash of the seed in use, if verify mode is on
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
+    status_inner(ctx).await
+}
+
+async fn status_inner(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+
+    let msg = match ctx.data().rng.status(guild_id) {
+        Some((hash, uses)) => format!(
+            "Verify mode is on, with the seed hash `{}`. It has been used by {} commands",
+            hash, uses
+        ),
+        None => "Verify mode is not on.".to_string(),
+    };
+    ctx.reply(msg).await?;
+    Ok(())
+}
@@ -1,7 +1,15 @@
-use crate::{Context, Error};
+use crate::{Context, Data, Error};
 use poise::serenity_prelude as serenity;
 
+/// The commands in this module
+pub fn commands() -> Vec<poise::Command<Data, Error>> {
+    vec![kick(), avatar(), uptime(), userinfo()]
+}
+
 /// Displays info about a user
+///
+/// Example: userinfo
+/// Example: userinfo @someone
 #[poise::command(prefix_command, slash_command)]
 pub async fn userinfo(
     ctx: Context<'_>,
@@ -47,6 +55,8 @@ pub async fn uptime(ctx: Context<'_>) -> Result<(), Error> {
 }
 
 /// Shows the avatar of a user
+///
+/// Example: avatar @someone
 #[poise::command(prefix_command, slash_command)]
 pub async fn avatar(
     ctx: Context<'_>,
@@ -72,7 +82,13 @@ pub async fn avatar(
 /// Kick a user
 ///
 /// Kicks a user from a guild
-#[poise::command(prefix_command, slash_command, guild_only, owners_only)]
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    check = "crate::checks::developer_only",
+    custom_data = "crate::checks::DeveloperOnly"
+)]
 pub async fn kick(
     ctx: Context<'_>,
     #[description = "the user to kick"] member: serenity::Member,
@@ -1,6 +1,52 @@
-use songbird::input::YoutubeDl;
+use poise::serenity_prelude as serenity;
+use songbird::{input::YoutubeDl, tracks::TrackHandle, Call};
+
+use crate::{Context, Data, Error};
+
+pub mod radio;
+
+/// The commands in this module
+pub fn commands() -> Vec<poise::Command<Data, Error>> {
+    vec![
+        join(),
+        leave(),
+        mute(),
+        unmute(),
+        deafen(),
+        undeafen(),
+        play(),
+        skip(),
+        stop(),
+        radio::radio(),
+    ]
+}
+
+/// What a song in the queue was requested with, stored in its track's typemap so the queue can be
+/// saved
+#[derive(Clone, Debug)]
+pub struct QueuedSong {
+    pub query: String,
+    /// Whether the query is searched for, rather than being a link
+    pub search: bool,
+}
+
+impl serenity::prelude::TypeMapKey for QueuedSong {
+    type Value = QueuedSong;
+}
 
-use crate::{Context, Error};
+impl QueuedSong {
+    /// Adds the song to the end of the call's queue
+    pub async fn enqueue(self, call: &mut Call, http: reqwest::Client) -> TrackHandle {
+        let source = if self.search {
+            YoutubeDl::new_search(http, self.query.clone())
+        } else {
+            YoutubeDl::new(http, self.query.clone())
+        };
+        let handle = call.enqueue_input(source.into()).await;
+        handle.typemap().write().await.insert::<QueuedSong>(self);
+        handle
+    }
+}
 
 /// Joins a voice channel
 #[poise::command(slash_command, prefix_command, guild_only)]
@@ -15,6 +61,12 @@ pub async fn join(ctx: Context<'_>) -> Result<(), Error> {
         (guild_id, channel_id)
     };
 
+    if radio::is_radio_guild(&ctx.data().radio, guild_id) {
+        ctx.reply("Radio mode is on, turn it off to move me.")
+            .await?;
+        return Ok(());
+    }
+
     let c = match channel_id {
         None => {
             ctx.reply("You are not in a voice channel.").await?;
@@ -34,6 +86,12 @@ pub async fn join(ctx: Context<'_>) -> Result<(), Error> {
 pub async fn leave(ctx: Context<'_>) -> Result<(), Error> {
     let guild_id = ctx.guild().unwrap().id;
 
+    if radio::is_radio_guild(&ctx.data().radio, guild_id) {
+        ctx.reply("Radio mode is on, turn it off to make me leave.")
+            .await?;
+        return Ok(());
+    }
+
     let manager = songbird::get(ctx.serenity_context()).await.unwrap().clone();
     match manager.remove(guild_id).await {
         Ok(_) => (),
@@ -45,8 +103,8 @@ pub async fn leave(ctx: Context<'_>) -> Result<(), Error> {
                 return Ok(());
             }
             songbird::error::JoinError::TimedOut => todo!(),
-            songbird::error::JoinError::Driver(error) => todo!(),
-            songbird::error::JoinError::Serenity(try_send_error) => todo!(),
+            songbird::error::JoinError::Driver(_error) => todo!(),
+            songbird::error::JoinError::Serenity(_try_send_error) => todo!(),
             _ => todo!(),
         },
     };
@@ -155,6 +213,10 @@ pub async fn undeafen(ctx: Context<'_>) -> Result<(), Error> {
 }
 
 /// Plays a song
+///
+/// Joins your voice channel if the bot isn't in one already
+///
+/// Example: play https://www.youtube.com/watch?v=dQw4w9WgXcQ
 #[poise::command(slash_command, prefix_command, guild_only)]
 pub async fn play(
     ctx: Context<'_>,
@@ -172,6 +234,12 @@ pub async fn play(
         (guild_id, channel_id)
     };
 
+    if radio::is_radio_guild(&ctx.data().radio, guild_id) {
+        ctx.reply("Radio mode is on, turn it off to play something else.")
+            .await?;
+        return Ok(());
+    }
+
     let search_for_song = !song.starts_with("http");
 
     let http_client = ctx.data().http.clone();
@@ -183,18 +251,17 @@ pub async fn play(
     if let Some(handler_lock) = manager.get(guild_id) {
         let mut handler = handler_lock.lock().await;
 
-        let src = if search_for_song {
-            YoutubeDl::new_search(http_client, song)
-        } else {
-            YoutubeDl::new(http_client, song)
+        let song = QueuedSong {
+            query: song,
+            search: search_for_song,
         };
 
         if handler.queue().current().is_some() {
-            let th = handler.enqueue(src.clone().into()).await;
+            let th = song.enqueue(&mut handler, http_client).await;
             let info = th.get_info().await?;
             ctx.reply(format!("Queued: {:?}", info)).await?;
         } else {
-            let th = handler.enqueue(src.clone().into()).await;
+            let th = song.enqueue(&mut handler, http_client).await;
             let info = th.get_info().await?;
             ctx.reply(format!("Now playing: {:?}", info)).await?;
         }
@@ -208,12 +275,18 @@ pub async fn play(
 pub async fn stop(ctx: Context<'_>) -> Result<(), Error> {
     let guild_id = ctx.guild_id().expect("guild only command");
 
+    if radio::is_radio_guild(&ctx.data().radio, guild_id) {
+        ctx.reply("Radio mode is on, turn it off to stop playing.")
+            .await?;
+        return Ok(());
+    }
+
     let manager = songbird::get(ctx.serenity_context()).await.unwrap().clone();
 
     if let Some(handler_lock) = manager.get(guild_id) {
         let mut handler = handler_lock.lock().await;
 
-        let _ = handler.stop();
+        handler.stop();
     }
 
     if manager.get(guild_id).is_some() {
@@ -0,0 +1,367 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use poise::serenity_prelude::{self as serenity, async_trait};
+use songbird::{
+    events::context_data::DisconnectReason,
+    input::{HttpRequest, Input, YoutubeDl},
+    CoreEvent, Event, EventContext, EventHandler, Songbird, TrackEvent,
+};
+
+#[cfg(feature = "database")]
+use crate::db;
+use crate::{
+    config::{Config, RadioConfig},
+    Context, Error,
+};
+
+/// The guilds that are currently in radio mode, along with what they are playing
+pub type RadioGuilds = Arc<RwLock<HashMap<serenity::GuildId, RadioConfig>>>;
+
+/// How long to wait before reconnecting or restarting a station that stopped
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Returns whether the guild is in radio mode
+///
+/// Radio guilds must stay connected, so anything that would disconnect the bot should check this first
+pub fn is_radio_guild(radios: &RadioGuilds, guild_id: serenity::GuildId) -> bool {
+    radios.read().unwrap().contains_key(&guild_id)
+}
+
+/// The radios to start with, which are the configured ones and the stations set with `radio on`
+///
+/// A station set with `radio on` replaces the configured radio of its guild
+pub async fn load(config: &Config) -> HashMap<serenity::GuildId, RadioConfig> {
+    let configured = config.radio.iter().flatten().cloned();
+
+    #[cfg(feature = "database")]
+    let saved = match db::radio_stations::all().await {
+        Ok(saved) => saved,
+        Err(e) => {
+            tracing::warn!("failed to load the saved radio stations: {}", e);
+            Vec::new()
+        }
+    };
+    #[cfg(not(feature = "database"))]
+    let saved = Vec::new();
+
+    configured
+        .chain(saved)
+        .map(|radio| (radio.guild, radio))
+        .collect()
+}
+
+/// Connects to the radio channel of a guild and starts playing if nothing is playing already
+pub async fn start(
+    manager: Arc<Songbird>,
+    http: reqwest::Client,
+    radios: RadioGuilds,
+    guild_id: serenity::GuildId,
+) -> Result<(), Error> {
+    let Some(radio) = radios.read().unwrap().get(&guild_id).cloned() else {
+        return Ok(());
+    };
+
+    let handler_lock = manager.join(guild_id, radio.channel).await?;
+    let mut handler = handler_lock.lock().await;
+
+    // Events are kept on the call between reconnects, so clear them to avoid handling everything twice
+    handler.remove_all_global_events();
+    let events = RadioEvents {
+        guild_id,
+        manager: manager.clone(),
+        http: http.clone(),
+        radios: radios.clone(),
+    };
+    handler.add_global_event(Event::Track(TrackEvent::End), events.clone());
+    handler.add_global_event(Event::Track(TrackEvent::Error), events.clone());
+    handler.add_global_event(Event::Core(CoreEvent::DriverDisconnect), events);
+
+    if handler.queue().is_empty() {
+        for source in sources(&http, &radio) {
+            handler.enqueue_input(source).await;
+        }
+    }
+    drop(handler);
+
+    tracing::info!(
+        "radio mode started in guild: {} channel: {}",
+        guild_id,
+        radio.channel
+    );
+    Ok(())
+}
+
+/// Queues the station or playlist again once the queue has run out
+async fn refill(
+    manager: Arc<Songbird>,
+    http: reqwest::Client,
+    radios: RadioGuilds,
+    guild_id: serenity::GuildId,
+) {
+    let Some(radio) = radios.read().unwrap().get(&guild_id).cloned() else {
+        return;
+    };
+    let Some(handler_lock) = manager.get(guild_id) else {
+        return;
+    };
+
+    let mut handler = handler_lock.lock().await;
+    if handler.queue().is_empty() {
+        for source in sources(&http, &radio) {
+            handler.enqueue_input(source).await;
+        }
+    }
+}
+
+/// Starts every radio that isn't currently connected
+///
+/// This is called whenever the gateway (re)connects, as voice connections don't survive a new session
+pub async fn ensure_connected(manager: Arc<Songbird>, http: reqwest::Client, radios: RadioGuilds) {
+    let guilds: Vec<_> = radios.read().unwrap().keys().copied().collect();
+
+    for guild_id in guilds {
+        let connected = match manager.get(guild_id) {
+            Some(call) => call.lock().await.current_connection().is_some(),
+            None => false,
+        };
+        if connected {
+            continue;
+        }
+
+        if let Err(e) = start(manager.clone(), http.clone(), radios.clone(), guild_id).await {
+            tracing::warn!("failed to start radio in guild: {}: {}", guild_id, e);
+        }
+    }
+}
+
+/// Turns radio mode off for a guild and leaves the voice channel
+pub async fn stop(manager: Arc<Songbird>, radios: RadioGuilds, guild_id: serenity::GuildId) {
+    radios.write().unwrap().remove(&guild_id);
+
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let mut handler = handler_lock.lock().await;
+        handler.remove_all_global_events();
+        handler.queue().stop();
+    }
+    let _ = manager.remove(guild_id).await;
+}
+
+fn sources(http: &reqwest::Client, radio: &RadioConfig) -> Vec<Input> {
+    if let Some(station) = &radio.station {
+        vec![HttpRequest::new(http.clone(), station.clone()).into()]
+    } else {
+        radio
+            .playlist
+            .iter()
+            .flatten()
+            .map(|song| YoutubeDl::new(http.clone(), song.clone()).into())
+            .collect()
+    }
+}
+
+/// Keeps a radio call alive by refilling the queue and rejoining after disconnects
+#[derive(Clone)]
+struct RadioEvents {
+    guild_id: serenity::GuildId,
+    manager: Arc<Songbird>,
+    http: reqwest::Client,
+    radios: RadioGuilds,
+}
+
+#[async_trait]
+impl EventHandler for RadioEvents {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        if !is_radio_guild(&self.radios, self.guild_id) {
+            return Some(Event::Cancel);
+        }
+
+        let events = self.clone();
+        match ctx {
+            EventContext::DriverDisconnect(data) => {
+                if data.reason == Some(DisconnectReason::Requested) {
+                    return None;
+                }
+                tracing::warn!(
+                    "radio in guild: {} disconnected ({:?}), reconnecting",
+                    self.guild_id,
+                    data.reason
+                );
+                tokio::spawn(async move {
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    let guild_id = events.guild_id;
+                    if let Err(e) =
+                        start(events.manager, events.http, events.radios, guild_id).await
+                    {
+                        tracing::warn!("failed to reconnect radio in guild: {}: {}", guild_id, e);
+                    }
+                });
+            }
+            EventContext::Track(_) => {
+                // Wait a little so a station that keeps failing doesn't get retried in a tight loop
+                tokio::spawn(async move {
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    refill(events.manager, events.http, events.radios, events.guild_id).await;
+                });
+            }
+            _ => (),
+        }
+
+        None
+    }
+}
+
+/// Controls the 24/7 radio
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("on", "off", "status")
+)]
+pub async fn radio(ctx: Context<'_>) -> Result<(), Error> {
+    status_inner(ctx).await
+}
+
+/// Starts the radio, using the configured station if none is given
+///
+/// A station given here is kept on after a restart, until the radio is turned off
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn on(
+    ctx: Context<'_>,
+    #[description = "the link of the station to play"] station: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+
+    let radio = match &station {
+        Some(station) => {
+            let channel_id = ctx
+                .guild()
+                .unwrap()
+                .voice_states
+                .get(&ctx.author().id)
+                .and_then(|voice_state| voice_state.channel_id);
+            let Some(channel) = channel_id else {
+                ctx.reply("You are not in a voice channel.").await?;
+                return Ok(());
+            };
+            RadioConfig {
+                guild: guild_id,
+                channel,
+                station: Some(station.clone()),
+                playlist: None,
+            }
+        }
+        None => {
+            let configured = ctx
+                .data()
+                .config
+                .read()
+                .unwrap()
+                .radio
+                .iter()
+                .flatten()
+                .find(|r| r.guild == guild_id)
+                .cloned();
+            let Some(radio) = configured else {
+                ctx.reply(
+                    "There is no radio configured for this guild, provide a station to play.",
+                )
+                .await?;
+                return Ok(());
+            };
+            radio
+        }
+    };
+
+    // Saved so the station comes back after a restart, while the configured radio is read from the config
+    #[cfg(feature = "database")]
+    match &station {
+        Some(station) => db::radio_stations::set(guild_id, radio.channel, station).await?,
+        None => db::radio_stations::remove(guild_id).await?,
+    }
+
+    let channel = radio.channel;
+    let radios = ctx.data().radio.clone();
+    radios.write().unwrap().insert(guild_id, radio);
+
+    // Clear anything that was playing before so the station starts straight away
+    let manager = songbird::get(ctx.serenity_context()).await.unwrap().clone();
+    if let Some(handler_lock) = manager.get(guild_id) {
+        handler_lock.lock().await.queue().stop();
+    }
+
+    start(manager, ctx.data().http.clone(), radios, guild_id).await?;
+    ctx.reply(format!("Radio mode is on in <#{}>.", channel))
+        .await?;
+
+    Ok(())
+}
+
+/// Stops the radio and leaves the voice channel
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn off(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+
+    if !is_radio_guild(&ctx.data().radio, guild_id) {
+        ctx.reply("Radio mode is not on.").await?;
+        return Ok(());
+    }
+
+    #[cfg(feature = "database")]
+    db::radio_stations::remove(guild_id).await?;
+
+    let manager = songbird::get(ctx.serenity_context()).await.unwrap().clone();
+    stop(manager, ctx.data().radio.clone(), guild_id).await;
+    ctx.reply("Radio mode is off.").await?;
+
+    Ok(())
+}
+
+/// Shows what the radio is playing
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
+    status_inner(ctx).await
+}
+
+async fn status_inner(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().expect("guild only command");
+
+    let radio = ctx.data().radio.read().unwrap().get(&guild_id).cloned();
+    let msg = match radio {
+        None => "Radio mode is not on.".to_string(),
+        Some(RadioConfig {
+            channel,
+            station: Some(station),
+            ..
+        }) => format!("Playing <{}> in <#{}>.", station, channel),
+        Some(RadioConfig {
+            channel, playlist, ..
+        }) => format!(
+            "Playing a playlist of {} songs in <#{}>.",
+            playlist.unwrap_or_default().len(),
+            channel
+        ),
+    };
+    ctx.reply(msg).await?;
+
+    Ok(())
+}
@@ -0,0 +1,759 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use poise::serenity_prelude as serenity;
+
+use crate::{
+    cli::ConfigArgs,
+    intents,
+    secret::{self, Secret},
+};
+
+/// The config, shared so that it can be swapped out when it's reloaded
+pub type SharedConfig = Arc<RwLock<Config>>;
+
+/// Settings that are picked up by a reload, anything else needs a restart to take effect
+const RELOADABLE: &[&str] = &[
+    "prefixes",
+    "developers",
+    "developer_guilds",
+    "error_channel",
+    "api_token",
+    "cooldowns",
+];
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, Default, PartialEq)]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<Secret<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_url: Option<Secret<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub developers: Option<BTreeSet<serenity::UserId>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub developer_guilds: Option<BTreeSet<serenity::GuildId>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefixes: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mention_as_prefix: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_self_messages: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_bot_messages: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub case_sensitive: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub radio: Option<Vec<RadioConfig>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_registration: Option<CommandRegistration>,
+    /// Where unexpected command errors are reported, so developers see them without reading the logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_channel: Option<serenity::ChannelId>,
+    /// Where Prometheus metrics are served, e.g. 127.0.0.1:9100. They aren't served when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics_address: Option<std::net::SocketAddr>,
+    /// Where the health checks and admin API are served. They aren't served when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_address: Option<std::net::SocketAddr>,
+    /// The bearer token needed for the admin API, which is turned off without one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_token: Option<Secret<String>>,
+    /// The gateway intents to connect with, e.g. guilds or message_content, instead of working them
+    /// out from the commands
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intents: Option<Vec<String>>,
+    /// How long commands have to wait between uses, by command or category name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cooldowns: Option<BTreeMap<String, Cooldown>>,
+    /// Seeds the random commands so their results can be reproduced, they're truly random when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rng_seed: Option<u64>,
+}
+
+/// Where application commands are registered when the bot starts
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum CommandRegistration {
+    /// Register commands for every guild, which can take a while to show up
+    #[default]
+    Global,
+    /// Only register commands in the developer guilds, where they update instantly
+    DeveloperGuilds,
+    /// Don't register commands
+    Off,
+}
+
+/// A guild that keeps the bot connected to a voice channel, playing music around the clock
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct RadioConfig {
+    pub guild: serenity::GuildId,
+    pub channel: serenity::ChannelId,
+    /// A link to a stream that is played directly
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub station: Option<String>,
+    /// Songs that are played on repeat when no station is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playlist: Option<Vec<String>>,
+}
+
+/// The seconds a command has to wait between uses, for each bucket it's counted in
+///
+/// A bucket without a cooldown doesn't limit the command
+#[derive(Clone, Copy, Debug, Default, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct Cooldown {
+    /// Between uses by the same user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<u64>,
+    /// Between uses in the same guild
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild: Option<u64>,
+    /// Between uses by anyone, anywhere
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub global: Option<u64>,
+}
+
+impl Cooldown {
+    /// Fills in the buckets that aren't set with the ones from `other`
+    pub fn or(self, other: Cooldown) -> Cooldown {
+        Cooldown {
+            user: self.user.or(other.user),
+            guild: self.guild.or(other.guild),
+            global: self.global.or(other.global),
+        }
+    }
+}
+
+/// The formats a config file can be written in, picked by the file's extension
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Files without a known extension are treated as JSON
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("toml") => ConfigFormat::Toml,
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Json,
+        }
+    }
+
+    pub fn serialize(self, config: &Config) -> String {
+        match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(config).unwrap(),
+            ConfigFormat::Toml => toml::to_string_pretty(config).unwrap(),
+            ConfigFormat::Yaml => serde_yaml::to_string(config).unwrap(),
+        }
+    }
+
+    fn parse(self, s: &str) -> Result<Config, ConfigError> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(s).map_err(|e| ConfigError::Parse {
+                line: Some(e.line()),
+                column: Some(e.column()),
+                message: without_location(&e.to_string()),
+            }),
+            ConfigFormat::Toml => toml::from_str(s).map_err(|e| {
+                let (line, column) = match e.span() {
+                    Some(span) => {
+                        let (line, column) = line_column(s, span.start);
+                        (Some(line), Some(column))
+                    }
+                    None => (None, None),
+                };
+                ConfigError::Parse {
+                    line,
+                    column,
+                    message: e.message().to_string(),
+                }
+            }),
+            ConfigFormat::Yaml => serde_yaml::from_str(s).map_err(|e| ConfigError::Parse {
+                line: e.location().map(|l| l.line()),
+                column: e.location().map(|l| l.column()),
+                message: without_location(&e.to_string()),
+            }),
+        }
+    }
+}
+
+/// Removes the location that JSON and YAML errors add to their messages, as it's reported separately
+fn without_location(message: &str) -> String {
+    match message.split_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => message.to_string(),
+    }
+}
+
+/// Converts a byte offset into a 1-based line and column
+fn line_column(s: &str, offset: usize) -> (usize, usize) {
+    let before = &s[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(std::io::Error),
+    Parse {
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    /// A `${VAR}` reference to an environment variable that isn't set
+    MissingEnv {
+        field: &'static str,
+        var: String,
+    },
+    /// Everything that's wrong with the config, as found by [`Config::problems`]
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(e) => write!(f, "failed to read the config file: {}", e),
+            ConfigError::Parse {
+                line: Some(line),
+                column: Some(column),
+                message,
+            } => write!(
+                f,
+                "failed to parse the config file at line {}, column {}: {}",
+                line, column, message
+            ),
+            ConfigError::Parse { message, .. } => {
+                write!(f, "failed to parse the config file: {}", message)
+            }
+            ConfigError::MissingEnv { field, var } => write!(
+                f,
+                "the environment variable {} used by {} is not set",
+                var, field
+            ),
+            ConfigError::Invalid(problems) => {
+                write!(f, "the config is invalid: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Reads the config file, if there is one, and applies the CLI arguments on top of it
+///
+/// The config is only returned if it's valid
+pub fn load(args: &ConfigArgs) -> Result<Config, ConfigError> {
+    let (config, problems) = load_unchecked(args)?;
+    if !problems.is_empty() {
+        return Err(ConfigError::Invalid(problems));
+    }
+
+    Ok(config)
+}
+
+/// Loads the config in the same way as [`load`], but returns it alongside its problems instead of rejecting it
+pub fn load_unchecked(args: &ConfigArgs) -> Result<(Config, Vec<String>), ConfigError> {
+    let mut config: Config = match config_file(args) {
+        Some(f) => {
+            let s = std::fs::read_to_string(&f).map_err(ConfigError::Read)?;
+            let mut config = ConfigFormat::from_path(&f).parse(&s)?;
+            config.interpolate_env()?;
+            config
+        }
+        None => Config::default(),
+    };
+
+    let mut problems = merge_overrides(&mut config, args);
+    problems.extend(config.problems());
+
+    Ok((config, problems))
+}
+
+/// Loads the config again and swaps it in, returning a description of everything that changed
+///
+/// The current config is kept if the new one can't be loaded
+pub fn reload(shared: &SharedConfig, args: &ConfigArgs) -> Result<Vec<String>, ConfigError> {
+    let new = load(args)?;
+
+    let mut current = shared.write().unwrap();
+    let changes = diff(&current, &new);
+    *current = new;
+
+    Ok(changes)
+}
+
+impl Config {
+    /// Replaces `${VAR}` references in string values with the value of the environment variable
+    fn interpolate_env(&mut self) -> Result<(), ConfigError> {
+        if let Some(token) = &mut self.token {
+            *token = Secret::new(interpolate(token.expose(), "token")?);
+        }
+        if let Some(url) = &mut self.database_url {
+            *url = Secret::new(interpolate(url.expose(), "database_url")?);
+        }
+        if let Some(token) = &mut self.api_token {
+            *token = Secret::new(interpolate(token.expose(), "api_token")?);
+        }
+        for prefix in self.prefixes.iter_mut().flatten() {
+            *prefix = interpolate(prefix, "prefixes")?;
+        }
+        for radio in self.radio.iter_mut().flatten() {
+            if let Some(station) = &mut radio.station {
+                *station = interpolate(station, "radio.station")?;
+            }
+            for song in radio.playlist.iter_mut().flatten() {
+                *song = interpolate(song, "radio.playlist")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the config for settings that can't work, returning a description of each one
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        match &self.prefixes {
+            Some(prefixes) if !prefixes.is_empty() => {
+                if prefixes.iter().any(|p| p.is_empty()) {
+                    problems.push("prefixes can't be empty strings".to_string());
+                }
+            }
+            _ => problems.push(
+                "no prefix was provided, set the --prefix argument or the prefixes field in the config file"
+                    .to_string(),
+            ),
+        }
+
+        if self.allow_self_messages == Some(true) && self.allow_bot_messages != Some(true) {
+            problems.push(
+                "allow_self_messages has no effect unless allow_bot_messages is also enabled"
+                    .to_string(),
+            );
+        }
+
+        if self.command_registration == Some(CommandRegistration::DeveloperGuilds)
+            && self.developer_guilds.as_ref().is_none_or(|g| g.is_empty())
+        {
+            problems.push(
+                "command_registration is developer_guilds, but there are no developer guilds"
+                    .to_string(),
+            );
+        }
+
+        for name in self.intents.iter().flatten() {
+            if intents::parse(name).is_none() {
+                problems.push(format!("{:?} isn't a gateway intent", name));
+            }
+        }
+
+        for radio in self.radio.iter().flatten() {
+            match (&radio.station, &radio.playlist) {
+                (Some(_), Some(_)) => problems.push(format!(
+                    "the radio for guild {} has both a station and a playlist, only one can be used",
+                    radio.guild
+                )),
+                (None, None) => problems.push(format!(
+                    "the radio for guild {} needs either a station or a playlist",
+                    radio.guild
+                )),
+                (None, Some(playlist)) if playlist.is_empty() => problems.push(format!(
+                    "the radio for guild {} has an empty playlist",
+                    radio.guild
+                )),
+                _ => (),
+            }
+        }
+
+        problems
+    }
+}
+
+fn interpolate(value: &str, field: &'static str) -> Result<String, ConfigError> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let var = &rest[start + 2..start + len];
+        let env = std::env::var(var).map_err(|_| ConfigError::MissingEnv {
+            field,
+            var: var.to_string(),
+        })?;
+
+        out.push_str(&rest[..start]);
+        out.push_str(&env);
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+/// Lists the top level settings that differ between two configs
+///
+/// Secrets are compared using their real values, but are redacted in the descriptions
+fn diff(old: &Config, new: &Config) -> Vec<String> {
+    let to_value =
+        |config| serde_json::to_value(config).expect("the config can always be serialized");
+    let (old_exposed, new_exposed) = secret::exposed(|| (to_value(old), to_value(new)));
+    let old = to_value(old);
+    let new = to_value(new);
+    let null = serde_json::Value::Null;
+
+    let mut keys: Vec<_> = old
+        .as_object()
+        .unwrap()
+        .keys()
+        .chain(new.as_object().unwrap().keys())
+        .collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            if old_exposed.get(key) == new_exposed.get(key) {
+                return None;
+            }
+            let before = old.get(key).unwrap_or(&null);
+            let after = new.get(key).unwrap_or(&null);
+
+            let mut change = format!("{}: {} -> {}", key, before, after);
+            if !RELOADABLE.contains(&key.as_str()) {
+                change.push_str(" (requires a restart)");
+            }
+            Some(change)
+        })
+        .collect()
+}
+
+/// The prefix of the environment variables that override config file values
+const ENV_PREFIX: &str = "BOT697_";
+
+/// Settings that take priority over the config file, either from the environment or the CLI
+#[derive(Default)]
+struct Overrides {
+    prefix: Option<String>,
+    extra_prefixes: Vec<String>,
+    mention_as_prefix: Option<bool>,
+    allow_self_messages: Option<bool>,
+    allow_bot_messages: Option<bool>,
+    case_sensitive: Option<bool>,
+    developer_ids: Vec<String>,
+    developer_guilds: Vec<String>,
+}
+
+impl Overrides {
+    fn from_args(args: &ConfigArgs) -> Self {
+        Overrides {
+            prefix: args.prefix.clone(),
+            extra_prefixes: args.extra_prefix.clone(),
+            mention_as_prefix: args.mention_as_prefix(),
+            allow_self_messages: args.allow_self_messages(),
+            allow_bot_messages: args.allow_bot_messages(),
+            case_sensitive: args.case_sensitive(),
+            developer_ids: args.developer_id.clone(),
+            developer_guilds: args.developer_guild.clone(),
+        }
+    }
+
+    /// Reads the `BOT697_` environment variables, lists are separated by commas
+    fn from_env(problems: &mut Vec<String>) -> Self {
+        let mut bool_var = |name: &str| {
+            let value = env_var(name)?;
+            match value.to_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => Some(true),
+                "false" | "0" | "no" | "off" => Some(false),
+                _ => {
+                    problems.push(format!(
+                        "{}{} should be true or false, not {:?}",
+                        ENV_PREFIX, name, value
+                    ));
+                    None
+                }
+            }
+        };
+
+        Overrides {
+            mention_as_prefix: bool_var("MENTION_AS_PREFIX"),
+            allow_self_messages: bool_var("ALLOW_SELF_MESSAGES"),
+            allow_bot_messages: bool_var("ALLOW_BOT_MESSAGES"),
+            case_sensitive: bool_var("CASE_SENSITIVE"),
+            prefix: env_var("PREFIX"),
+            extra_prefixes: env_list("EXTRA_PREFIX"),
+            developer_ids: env_list("DEVELOPER_ID"),
+            developer_guilds: env_list("DEVELOPER_GUILD"),
+        }
+    }
+
+    /// Applies the overrides to the config, returning any values that couldn't be used
+    fn apply(self, config: &mut Config) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        // Create a list of all prefixes, with the main prefix first
+        let mut prefixes = Vec::new();
+        prefixes.extend(self.prefix);
+        prefixes.extend(config.prefixes.take().unwrap_or_default());
+        prefixes.extend(self.extra_prefixes);
+        config.prefixes = Some(prefixes);
+
+        if self.mention_as_prefix.is_some() {
+            config.mention_as_prefix = self.mention_as_prefix;
+        }
+        if self.allow_self_messages.is_some() {
+            config.allow_self_messages = self.allow_self_messages;
+        }
+        if self.allow_bot_messages.is_some() {
+            config.allow_bot_messages = self.allow_bot_messages;
+        }
+        if self.case_sensitive.is_some() {
+            config.case_sensitive = self.case_sensitive;
+        }
+
+        // Merge developer IDs
+        let mut dev_ids = config.developers.take().unwrap_or_default();
+        for id in self.developer_ids {
+            match id.parse::<u64>() {
+                Ok(parsed) => {
+                    dev_ids.insert(serenity::UserId::new(parsed));
+                }
+                Err(e) => problems.push(format!("the developer ID {:?} is invalid: {}", id, e)),
+            }
+        }
+        config.developers = Some(dev_ids);
+
+        // Merge developer guilds
+        let mut dev_guilds = config.developer_guilds.take().unwrap_or_default();
+        for id in self.developer_guilds {
+            match id.parse::<u64>() {
+                Ok(parsed) => {
+                    dev_guilds.insert(serenity::GuildId::new(parsed));
+                }
+                Err(e) => {
+                    problems.push(format!("the developer guild ID {:?} is invalid: {}", id, e))
+                }
+            }
+        }
+        config.developer_guilds = Some(dev_guilds);
+
+        problems
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(format!("{}{}", ENV_PREFIX, name))
+        .ok()
+        .filter(|v| !v.is_empty())
+}
+
+fn env_list(name: &str) -> Vec<String> {
+    env_var(name)
+        .map(|v| v.split(',').map(|s| s.trim().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// The config file given on the CLI, or in the environment
+pub fn config_file(args: &ConfigArgs) -> Option<String> {
+    args.config_file.clone().or_else(|| env_var("CONFIG_FILE"))
+}
+
+/// Applies the environment and then the CLI arguments to the config, so the CLI takes priority
+///
+/// Returns any values that couldn't be used
+fn merge_overrides(config: &mut Config, args: &ConfigArgs) -> Vec<String> {
+    let mut problems = Vec::new();
+    let env = Overrides::from_env(&mut problems);
+    problems.extend(env.apply(config));
+    problems.extend(Overrides::from_args(args).apply(config));
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use clap::Parser;
+
+    use super::*;
+
+    /// Held by tests that set environment variables, as tests run in parallel in one process
+    static ENV: Mutex<()> = Mutex::new(());
+
+    const VARS: &[&str] = &[
+        "MENTION_AS_PREFIX",
+        "ALLOW_SELF_MESSAGES",
+        "ALLOW_BOT_MESSAGES",
+        "CASE_SENSITIVE",
+        "PREFIX",
+        "EXTRA_PREFIX",
+        "DEVELOPER_ID",
+        "DEVELOPER_GUILD",
+        "CONFIG_FILE",
+    ];
+
+    #[derive(Parser)]
+    struct Args {
+        #[command(flatten)]
+        config: ConfigArgs,
+    }
+
+    fn config_args(args: &[&str]) -> ConfigArgs {
+        Args::parse_from(std::iter::once("bot").chain(args.iter().copied())).config
+    }
+
+    /// Runs `f` with only the given `BOT697_` variables set
+    fn with_env<T>(vars: &[(&str, &str)], f: impl FnOnce() -> T) -> T {
+        let _lock = ENV.lock().unwrap_or_else(|e| e.into_inner());
+        for name in VARS {
+            std::env::remove_var(format!("{}{}", ENV_PREFIX, name));
+        }
+        for (name, value) in vars {
+            std::env::set_var(format!("{}{}", ENV_PREFIX, name), value);
+        }
+        let result = f();
+        for (name, _) in vars {
+            std::env::remove_var(format!("{}{}", ENV_PREFIX, name));
+        }
+        result
+    }
+
+    #[test]
+    fn reads_env_booleans() {
+        let overrides = with_env(
+            &[
+                ("MENTION_AS_PREFIX", "yes"),
+                ("ALLOW_SELF_MESSAGES", "0"),
+                ("ALLOW_BOT_MESSAGES", "ON"),
+                ("CASE_SENSITIVE", "False"),
+            ],
+            || {
+                let mut problems = Vec::new();
+                let overrides = Overrides::from_env(&mut problems);
+                assert!(problems.is_empty(), "{:?}", problems);
+                overrides
+            },
+        );
+        assert_eq!(overrides.mention_as_prefix, Some(true));
+        assert_eq!(overrides.allow_self_messages, Some(false));
+        assert_eq!(overrides.allow_bot_messages, Some(true));
+        assert_eq!(overrides.case_sensitive, Some(false));
+    }
+
+    #[test]
+    fn reports_bad_env_booleans() {
+        let mut problems = Vec::new();
+        let overrides = with_env(
+            &[("CASE_SENSITIVE", "maybe"), ("ALLOW_BOT_MESSAGES", "")],
+            || Overrides::from_env(&mut problems),
+        );
+        // An empty variable counts as unset rather than as a problem
+        assert_eq!(overrides.case_sensitive, None);
+        assert_eq!(overrides.allow_bot_messages, None);
+        assert_eq!(
+            problems,
+            ["BOT697_CASE_SENSITIVE should be true or false, not \"maybe\""]
+        );
+    }
+
+    #[test]
+    fn unset_overrides_keep_the_file() {
+        let mut config = Config {
+            case_sensitive: Some(true),
+            allow_bot_messages: Some(false),
+            ..Default::default()
+        };
+        let problems = with_env(&[], || merge_overrides(&mut config, &config_args(&[])));
+        assert!(problems.is_empty());
+        assert_eq!(config.case_sensitive, Some(true));
+        assert_eq!(config.allow_bot_messages, Some(false));
+        assert_eq!(config.mention_as_prefix, None);
+    }
+
+    #[test]
+    fn cli_beats_env_beats_file_beats_default() {
+        let mut config = Config {
+            mention_as_prefix: Some(false),
+            allow_self_messages: Some(false),
+            allow_bot_messages: Some(false),
+            ..Default::default()
+        };
+        let args = config_args(&["--no-mention-as-prefix", "--allow-self-messages"]);
+        let problems = with_env(
+            &[
+                ("MENTION_AS_PREFIX", "true"),
+                ("ALLOW_BOT_MESSAGES", "true"),
+            ],
+            || merge_overrides(&mut config, &args),
+        );
+        assert!(problems.is_empty());
+
+        // The CLI over the env
+        assert_eq!(config.mention_as_prefix, Some(false));
+        // The CLI over the file
+        assert_eq!(config.allow_self_messages, Some(true));
+        // The env over the file
+        assert_eq!(config.allow_bot_messages, Some(true));
+        // Nothing set, so left for the default
+        assert_eq!(config.case_sensitive, None);
+    }
+
+    #[test]
+    fn combines_lists_from_every_source() {
+        let mut config = Config {
+            prefixes: Some(vec!["file".to_string()]),
+            developers: Some([serenity::UserId::new(1)].into()),
+            ..Default::default()
+        };
+        let args = config_args(&["--prefix", "cli", "--developer-id", "3"]);
+        let problems = with_env(
+            &[
+                ("PREFIX", "env"),
+                ("EXTRA_PREFIX", "a, b"),
+                ("DEVELOPER_ID", "2,nope"),
+            ],
+            || merge_overrides(&mut config, &args),
+        );
+
+        // The CLI prefix is the main one, ahead of the env's
+        assert_eq!(
+            config.prefixes.unwrap(),
+            ["cli", "env", "file", "a", "b"].map(String::from)
+        );
+        assert_eq!(
+            config.developers.unwrap(),
+            [1, 2, 3].map(serenity::UserId::new).into()
+        );
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("the developer ID \"nope\" is invalid"));
+    }
+
+    #[test]
+    fn loads_the_file_under_the_overrides() {
+        let path = std::env::temp_dir().join(format!("bot697-test-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "case_sensitive = true\nallow_self_messages = true\nprefixes = [\"!\"]\n",
+        )
+        .unwrap();
+
+        let args = config_args(&["--config-file", path.to_str().unwrap()]);
+        let result = with_env(&[("CASE_SENSITIVE", "off")], || load_unchecked(&args));
+        std::fs::remove_file(&path).unwrap();
+
+        let (config, _) = result.unwrap();
+        assert_eq!(config.case_sensitive, Some(false));
+        assert_eq!(config.allow_self_messages, Some(true));
+        assert_eq!(config.prefixes, Some(vec!["!".to_string()]));
+    }
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use crate::{config::Cooldown, Context, Error};
+
+/// The buckets a guild can set its own cooldowns for
+///
+/// The global bucket is shared by every guild, so it can only be set in the config
+#[cfg(feature = "database")]
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, PartialEq)]
+pub enum Bucket {
+    #[name = "user"]
+    User,
+    #[name = "guild"]
+    Guild,
+}
+
+#[cfg(feature = "database")]
+impl Bucket {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Bucket::User => "user",
+            Bucket::Guild => "guild",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "user" => Some(Bucket::User),
+            "guild" => Some(Bucket::Guild),
+            _ => None,
+        }
+    }
+}
+
+/// How long the user has to wait before they can run the command again, if at all
+///
+/// Cooldowns are tracked on the top level command, so they're shared by all of its subcommands
+pub async fn remaining(ctx: Context<'_>) -> Result<Option<Duration>, Error> {
+    let command = root(ctx);
+    let cooldown = cooldown(ctx).await?;
+    let config = poise::CooldownConfig {
+        user: cooldown.user.map(Duration::from_secs),
+        guild: cooldown.guild.map(Duration::from_secs),
+        global: cooldown.global.map(Duration::from_secs),
+        ..Default::default()
+    };
+
+    let tracker = command.cooldowns.lock().unwrap();
+    Ok(tracker.remaining_cooldown(ctx.cooldown_context(), &config))
+}
+
+/// Starts the cooldowns of the command that's about to run
+pub fn start(ctx: Context<'_>) {
+    let command = root(ctx);
+    command
+        .cooldowns
+        .lock()
+        .unwrap()
+        .start_cooldown(ctx.cooldown_context());
+}
+
+/// Works out the command's cooldown, preferring the guild's settings over the config, and settings for
+/// the command over its category
+async fn cooldown(ctx: Context<'_>) -> Result<Cooldown, Error> {
+    let command = root(ctx);
+    let names: Vec<&str> = std::iter::once(command.name.as_str())
+        .chain(command.category.as_deref())
+        .collect();
+
+    let mut cooldown = Cooldown::default();
+
+    #[cfg(feature = "database")]
+    if let Some(guild_id) = ctx.guild_id() {
+        for name in &names {
+            cooldown = cooldown.or(crate::db::cooldowns::overrides(guild_id, name).await?);
+        }
+    }
+
+    let config = ctx.data().config.read().unwrap();
+    for name in &names {
+        if let Some(configured) = config.cooldowns.as_ref().and_then(|c| c.get(*name)) {
+            cooldown = cooldown.or(*configured);
+        }
+    }
+
+    Ok(cooldown)
+}
+
+fn root<'a>(ctx: Context<'a>) -> &'a poise::Command<crate::Data, Error> {
+    ctx.parent_commands()
+        .first()
+        .copied()
+        .unwrap_or(ctx.command())
+}
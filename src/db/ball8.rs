@@ -0,0 +1,83 @@
+use poise::serenity_prelude as serenity;
+
+use super::get_database;
+use crate::commands::rng::ball8::{Answer, Kind};
+
+/// A guild's own 8ball answer, along with the ID it's removed by
+pub struct CustomAnswer {
+    pub id: i64,
+    pub answer: Answer,
+}
+
+/// The answers the guild has added, oldest first
+pub async fn answers(guild_id: serenity::GuildId) -> Result<Vec<CustomAnswer>, sqlx::Error> {
+    let rows: Vec<(i64, String, i64, String)> = sqlx::query_as(
+        "SELECT id, text, weight, kind FROM ball8_answers WHERE guild_id = ? ORDER BY id",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_all(get_database())
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, text, weight, kind)| {
+            Some(CustomAnswer {
+                id,
+                answer: Answer {
+                    text,
+                    weight: weight as u32,
+                    kind: Kind::parse(&kind)?,
+                },
+            })
+        })
+        .collect())
+}
+
+/// Adds an answer for the guild, returning its ID
+pub async fn add(guild_id: serenity::GuildId, answer: &Answer) -> Result<i64, sqlx::Error> {
+    let result =
+        sqlx::query("INSERT INTO ball8_answers (guild_id, text, weight, kind) VALUES (?, ?, ?, ?)")
+            .bind(guild_id.get() as i64)
+            .bind(&answer.text)
+            .bind(answer.weight as i64)
+            .bind(answer.kind.as_str())
+            .execute(get_database())
+            .await?;
+    Ok(result.last_insert_rowid())
+}
+
+/// Removes one of the guild's answers, returning whether it existed
+pub async fn remove(guild_id: serenity::GuildId, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM ball8_answers WHERE guild_id = ? AND id = ?")
+        .bind(guild_id.get() as i64)
+        .bind(id)
+        .execute(get_database())
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Whether the built-in answers are used in the guild, which they are unless turned off
+pub async fn builtin_enabled(guild_id: serenity::GuildId) -> Result<bool, sqlx::Error> {
+    let disabled: Option<(i64,)> =
+        sqlx::query_as("SELECT 1 FROM ball8_builtin_disabled WHERE guild_id = ?")
+            .bind(guild_id.get() as i64)
+            .fetch_optional(get_database())
+            .await?;
+    Ok(disabled.is_none())
+}
+
+pub async fn set_builtin_enabled(
+    guild_id: serenity::GuildId,
+    enabled: bool,
+) -> Result<(), sqlx::Error> {
+    let query = if enabled {
+        "DELETE FROM ball8_builtin_disabled WHERE guild_id = ?"
+    } else {
+        "INSERT OR IGNORE INTO ball8_builtin_disabled (guild_id) VALUES (?)"
+    };
+    sqlx::query(query)
+        .bind(guild_id.get() as i64)
+        .execute(get_database())
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use poise::serenity_prelude as serenity;
+
+use super::get_database;
+
+/// Somewhere a command is limited to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Restriction {
+    Channel(serenity::ChannelId),
+    Role(serenity::RoleId),
+}
+
+/// Whether any of the names, which can be commands or categories, are turned off in the guild
+pub async fn is_disabled(guild_id: serenity::GuildId, names: &[&str]) -> Result<bool, sqlx::Error> {
+    for name in names {
+        let disabled: Option<(i64,)> =
+            sqlx::query_as("SELECT 1 FROM disabled_commands WHERE guild_id = ? AND name = ?")
+                .bind(guild_id.get() as i64)
+                .bind(name)
+                .fetch_optional(get_database())
+                .await?;
+        if disabled.is_some() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+pub async fn set_disabled(
+    guild_id: serenity::GuildId,
+    name: &str,
+    disabled: bool,
+) -> Result<(), sqlx::Error> {
+    let query = if disabled {
+        "INSERT OR IGNORE INTO disabled_commands (guild_id, name) VALUES (?, ?)"
+    } else {
+        "DELETE FROM disabled_commands WHERE guild_id = ? AND name = ?"
+    };
+    sqlx::query(query)
+        .bind(guild_id.get() as i64)
+        .bind(name)
+        .execute(get_database())
+        .await?;
+    Ok(())
+}
+
+/// Everything that's turned off in the guild
+pub async fn disabled(guild_id: serenity::GuildId) -> Result<Vec<String>, sqlx::Error> {
+    let rows: Vec<(String,)> =
+        sqlx::query_as("SELECT name FROM disabled_commands WHERE guild_id = ? ORDER BY name")
+            .bind(guild_id.get() as i64)
+            .fetch_all(get_database())
+            .await?;
+    Ok(rows.into_iter().map(|(name,)| name).collect())
+}
+
+/// The restrictions on a command or category in the guild
+pub async fn restrictions(
+    guild_id: serenity::GuildId,
+    name: &str,
+) -> Result<Vec<Restriction>, sqlx::Error> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT kind, target_id FROM command_restrictions WHERE guild_id = ? AND name = ?",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(name)
+    .fetch_all(get_database())
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(kind, id)| match kind.as_str() {
+            "channel" => Some(Restriction::Channel(serenity::ChannelId::new(id as u64))),
+            "role" => Some(Restriction::Role(serenity::RoleId::new(id as u64))),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Every restriction in the guild, along with the command or category it applies to
+pub async fn all_restrictions(
+    guild_id: serenity::GuildId,
+) -> Result<Vec<(String, Restriction)>, sqlx::Error> {
+    let rows: Vec<(String, String, i64)> = sqlx::query_as(
+        "SELECT name, kind, target_id FROM command_restrictions WHERE guild_id = ? ORDER BY name",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_all(get_database())
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(name, kind, id)| match kind.as_str() {
+            "channel" => Some((
+                name,
+                Restriction::Channel(serenity::ChannelId::new(id as u64)),
+            )),
+            "role" => Some((name, Restriction::Role(serenity::RoleId::new(id as u64)))),
+            _ => None,
+        })
+        .collect())
+}
+
+pub async fn add_restriction(
+    guild_id: serenity::GuildId,
+    name: &str,
+    restriction: Restriction,
+) -> Result<(), sqlx::Error> {
+    let (kind, id) = match restriction {
+        Restriction::Channel(id) => ("channel", id.get()),
+        Restriction::Role(id) => ("role", id.get()),
+    };
+    sqlx::query(
+        "INSERT OR IGNORE INTO command_restrictions (guild_id, name, kind, target_id) VALUES (?, ?, ?, ?)",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(name)
+    .bind(kind)
+    .bind(id as i64)
+    .execute(get_database())
+    .await?;
+    Ok(())
+}
+
+/// Removes every restriction on a command or category, returning how many there were
+pub async fn clear_restrictions(
+    guild_id: serenity::GuildId,
+    name: &str,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM command_restrictions WHERE guild_id = ? AND name = ?")
+        .bind(guild_id.get() as i64)
+        .bind(name)
+        .execute(get_database())
+        .await?;
+    Ok(result.rows_affected())
+}
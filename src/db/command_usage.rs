@@ -0,0 +1,136 @@
+use poise::serenity_prelude as serenity;
+
+use super::get_database;
+
+/// Usage of one command, or everything when it's the total
+#[cfg(feature = "dev")]
+pub struct CommandStats {
+    pub name: String,
+    pub invocations: i64,
+    pub errors: i64,
+    pub average_latency_ms: f64,
+}
+
+/// Adds a command invocation to the hour it was run in
+pub async fn record(
+    command: &str,
+    guild_id: Option<serenity::GuildId>,
+    user_id: serenity::UserId,
+    latency_ms: u64,
+    error: bool,
+) -> Result<(), sqlx::Error> {
+    let hour = chrono::Utc::now().timestamp() / 3600 * 3600;
+    sqlx::query(
+        "INSERT INTO command_usage (hour, command, guild_id, user_id, invocations, errors, total_latency_ms)
+        VALUES (?, ?, ?, ?, 1, ?, ?)
+        ON CONFLICT (hour, command, guild_id, user_id) DO UPDATE SET
+            invocations = invocations + 1,
+            errors = errors + excluded.errors,
+            total_latency_ms = total_latency_ms + excluded.total_latency_ms",
+    )
+    .bind(hour)
+    .bind(command)
+    .bind(guild_id.map_or(0, |id| id.get() as i64))
+    .bind(user_id.get() as i64)
+    .bind(error as i64)
+    .bind(latency_ms as i64)
+    .execute(get_database())
+    .await?;
+    Ok(())
+}
+
+/// Usage of every command since the given unix timestamp
+#[cfg(feature = "dev")]
+pub async fn total(since: i64) -> Result<CommandStats, sqlx::Error> {
+    let (invocations, errors, latency): (i64, i64, i64) = sqlx::query_as(
+        "SELECT COALESCE(SUM(invocations), 0), COALESCE(SUM(errors), 0), COALESCE(SUM(total_latency_ms), 0)
+        FROM command_usage WHERE hour >= ?",
+    )
+    .bind(since)
+    .fetch_one(get_database())
+    .await?;
+
+    Ok(CommandStats {
+        name: "total".to_string(),
+        invocations,
+        errors,
+        average_latency_ms: average(latency, invocations),
+    })
+}
+
+/// The most used commands since the given unix timestamp
+#[cfg(feature = "dev")]
+pub async fn top_commands(since: i64, limit: u32) -> Result<Vec<CommandStats>, sqlx::Error> {
+    let rows: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+        "SELECT command, SUM(invocations) AS total, SUM(errors), SUM(total_latency_ms)
+        FROM command_usage WHERE hour >= ?
+        GROUP BY command ORDER BY total DESC LIMIT ?",
+    )
+    .bind(since)
+    .bind(limit)
+    .fetch_all(get_database())
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(name, invocations, errors, latency)| CommandStats {
+            name,
+            invocations,
+            errors,
+            average_latency_ms: average(latency, invocations),
+        })
+        .collect())
+}
+
+/// The guilds that used the most commands since the given unix timestamp, leaving out DMs
+#[cfg(feature = "dev")]
+pub async fn top_guilds(
+    since: i64,
+    limit: u32,
+) -> Result<Vec<(serenity::GuildId, i64)>, sqlx::Error> {
+    let rows: Vec<(i64, i64)> = sqlx::query_as(
+        "SELECT guild_id, SUM(invocations) AS total
+        FROM command_usage WHERE hour >= ? AND guild_id != 0
+        GROUP BY guild_id ORDER BY total DESC LIMIT ?",
+    )
+    .bind(since)
+    .bind(limit)
+    .fetch_all(get_database())
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, total)| (serenity::GuildId::new(id as u64), total))
+        .collect())
+}
+
+/// The users that used the most commands since the given unix timestamp
+#[cfg(feature = "dev")]
+pub async fn top_users(
+    since: i64,
+    limit: u32,
+) -> Result<Vec<(serenity::UserId, i64)>, sqlx::Error> {
+    let rows: Vec<(i64, i64)> = sqlx::query_as(
+        "SELECT user_id, SUM(invocations) AS total
+        FROM command_usage WHERE hour >= ?
+        GROUP BY user_id ORDER BY total DESC LIMIT ?",
+    )
+    .bind(since)
+    .bind(limit)
+    .fetch_all(get_database())
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, total)| (serenity::UserId::new(id as u64), total))
+        .collect())
+}
+
+#[cfg(feature = "dev")]
+fn average(total: i64, count: i64) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        total as f64 / count as f64
+    }
+}
@@ -0,0 +1,70 @@
+use poise::serenity_prelude as serenity;
+
+use super::get_database;
+use crate::{config::Cooldown, cooldowns::Bucket};
+
+/// The cooldowns the guild has set for a command or category, with the buckets it hasn't set left empty
+pub async fn overrides(guild_id: serenity::GuildId, name: &str) -> Result<Cooldown, sqlx::Error> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT bucket, seconds FROM cooldown_overrides WHERE guild_id = ? AND name = ?",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(name)
+    .fetch_all(get_database())
+    .await?;
+
+    let mut cooldown = Cooldown::default();
+    for (bucket, seconds) in rows {
+        match Bucket::parse(&bucket) {
+            Some(Bucket::User) => cooldown.user = Some(seconds as u64),
+            Some(Bucket::Guild) => cooldown.guild = Some(seconds as u64),
+            None => (),
+        }
+    }
+    Ok(cooldown)
+}
+
+/// Every cooldown the guild has set, along with the command or category it applies to
+pub async fn all_overrides(
+    guild_id: serenity::GuildId,
+) -> Result<Vec<(String, Bucket, u64)>, sqlx::Error> {
+    let rows: Vec<(String, String, i64)> = sqlx::query_as(
+        "SELECT name, bucket, seconds FROM cooldown_overrides WHERE guild_id = ? ORDER BY name, bucket",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_all(get_database())
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(name, bucket, seconds)| {
+            Bucket::parse(&bucket).map(|bucket| (name, bucket, seconds as u64))
+        })
+        .collect())
+}
+
+/// Sets the guild's cooldown for a command or category, or goes back to the config's when it's `None`
+pub async fn set_override(
+    guild_id: serenity::GuildId,
+    name: &str,
+    bucket: Bucket,
+    seconds: Option<u64>,
+) -> Result<(), sqlx::Error> {
+    let query = match seconds {
+        Some(seconds) => sqlx::query(
+            "INSERT OR REPLACE INTO cooldown_overrides (guild_id, name, bucket, seconds) VALUES (?, ?, ?, ?)",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(name)
+        .bind(bucket.as_str())
+        .bind(seconds as i64),
+        None => sqlx::query(
+            "DELETE FROM cooldown_overrides WHERE guild_id = ? AND name = ? AND bucket = ?",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(name)
+        .bind(bucket.as_str()),
+    };
+    query.execute(get_database()).await?;
+    Ok(())
+}
@@ -3,6 +3,18 @@ use std::sync::OnceLock;
 
 use sqlx::{Pool, Sqlite};
 
+#[cfg(feature = "rng")]
+pub mod ball8;
+pub mod command_settings;
+pub mod command_usage;
+pub mod cooldowns;
+#[cfg(feature = "voice")]
+pub mod radio_stations;
+#[cfg(feature = "rng")]
+pub mod rng_commitments;
+#[cfg(feature = "voice")]
+pub mod saved_queues;
+
 static DATABASE_POOL: OnceLock<Pool<Sqlite>> = OnceLock::new();
 
 pub fn get_database() -> &'static Pool<Sqlite> {
@@ -16,3 +28,8 @@ pub fn set_database(db: Pool<Sqlite>) {
         .set(db)
         .unwrap_or_else(|_| panic!("called 'set_db()' more than once"))
 }
+
+/// Brings the database schema up to date
+pub async fn migrate() -> Result<(), sqlx::migrate::MigrateError> {
+    sqlx::migrate!().run(get_database()).await
+}
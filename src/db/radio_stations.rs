@@ -0,0 +1,47 @@
+use poise::serenity_prelude as serenity;
+
+use super::get_database;
+use crate::config::RadioConfig;
+
+/// Every station set with `radio on`
+pub async fn all() -> Result<Vec<RadioConfig>, sqlx::Error> {
+    let rows: Vec<(i64, i64, String)> =
+        sqlx::query_as("SELECT guild_id, channel_id, station FROM radio_stations")
+            .fetch_all(get_database())
+            .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(guild_id, channel_id, station)| RadioConfig {
+            guild: serenity::GuildId::new(guild_id as u64),
+            channel: serenity::ChannelId::new(channel_id as u64),
+            station: Some(station),
+            playlist: None,
+        })
+        .collect())
+}
+
+/// Saves the station a guild plays, replacing any it had before
+pub async fn set(
+    guild_id: serenity::GuildId,
+    channel_id: serenity::ChannelId,
+    station: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT OR REPLACE INTO radio_stations (guild_id, channel_id, station) VALUES (?, ?, ?)",
+    )
+    .bind(guild_id.get() as i64)
+    .bind(channel_id.get() as i64)
+    .bind(station)
+    .execute(get_database())
+    .await?;
+    Ok(())
+}
+
+pub async fn remove(guild_id: serenity::GuildId) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM radio_stations WHERE guild_id = ?")
+        .bind(guild_id.get() as i64)
+        .execute(get_database())
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use poise::serenity_prelude as serenity;
+
+use super::get_database;
+
+/// A guild's commitment as it was last saved
+pub struct SavedCommitment {
+    pub guild_id: serenity::GuildId,
+    pub seed: [u8; 32],
+    pub uses: u64,
+    /// The word position of the generator, so it carries on where it left off
+    pub word_pos: u128,
+}
+
+/// Every saved commitment, skipping any that can't be read back
+pub async fn all() -> Result<Vec<SavedCommitment>, sqlx::Error> {
+    let rows: Vec<(i64, Vec<u8>, i64, String)> =
+        sqlx::query_as("SELECT guild_id, seed, uses, word_pos FROM rng_commitments")
+            .fetch_all(get_database())
+            .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(guild_id, seed, uses, word_pos)| {
+            Some(SavedCommitment {
+                guild_id: serenity::GuildId::new(guild_id as u64),
+                seed: seed.try_into().ok()?,
+                uses: uses as u64,
+                word_pos: word_pos.parse().ok()?,
+            })
+        })
+        .collect())
+}
+
+/// Saves a new commitment for the guild, replacing any old one
+pub async fn insert(guild_id: serenity::GuildId, seed: &[u8; 32]) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT OR REPLACE INTO rng_commitments (guild_id, seed) VALUES (?, ?)")
+        .bind(guild_id.get() as i64)
+        .bind(seed.as_slice())
+        .execute(get_database())
+        .await?;
+    Ok(())
+}
+
+/// Records how far the guild's generator has got
+///
+/// Updates can arrive out of order, so one is only applied if it's further along than what's saved,
+/// and only to the commitment with the same seed
+pub async fn update(saved: &SavedCommitment) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE rng_commitments SET uses = ?, word_pos = ? WHERE guild_id = ? AND seed = ? AND uses < ?",
+    )
+    .bind(saved.uses as i64)
+    .bind(saved.word_pos.to_string())
+    .bind(saved.guild_id.get() as i64)
+    .bind(saved.seed.as_slice())
+    .bind(saved.uses as i64)
+    .execute(get_database())
+    .await?;
+    Ok(())
+}
+
+/// Removes the guild's commitment once it has been revealed
+pub async fn delete(guild_id: serenity::GuildId) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM rng_commitments WHERE guild_id = ?")
+        .bind(guild_id.get() as i64)
+        .execute(get_database())
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use poise::serenity_prelude as serenity;
+
+use super::get_database;
+use crate::commands::voice::QueuedSong;
+
+/// Replaces the saved queue of a guild
+pub async fn save(
+    guild_id: serenity::GuildId,
+    channel_id: serenity::ChannelId,
+    songs: &[QueuedSong],
+) -> Result<(), sqlx::Error> {
+    let mut transaction = get_database().begin().await?;
+
+    sqlx::query("DELETE FROM saved_queues WHERE guild_id = ?")
+        .bind(guild_id.get() as i64)
+        .execute(&mut *transaction)
+        .await?;
+    for (position, song) in songs.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO saved_queues (guild_id, channel_id, position, query, search) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(channel_id.get() as i64)
+        .bind(position as i64)
+        .bind(&song.query)
+        .bind(song.search)
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    transaction.commit().await
+}
+
+/// Removes the saved queue of a guild and returns it, with the channel it was playing in
+pub async fn take(
+    guild_id: serenity::GuildId,
+) -> Result<Option<(serenity::ChannelId, Vec<QueuedSong>)>, sqlx::Error> {
+    let mut transaction = get_database().begin().await?;
+
+    let rows: Vec<(i64, String, bool)> = sqlx::query_as(
+        "SELECT channel_id, query, search FROM saved_queues WHERE guild_id = ? ORDER BY position",
+    )
+    .bind(guild_id.get() as i64)
+    .fetch_all(&mut *transaction)
+    .await?;
+    sqlx::query("DELETE FROM saved_queues WHERE guild_id = ?")
+        .bind(guild_id.get() as i64)
+        .execute(&mut *transaction)
+        .await?;
+    transaction.commit().await?;
+
+    let Some((channel_id, _, _)) = rows.first() else {
+        return Ok(None);
+    };
+    let channel_id = serenity::ChannelId::new(*channel_id as u64);
+    let songs = rows
+        .into_iter()
+        .map(|(_, query, search)| QueuedSong { query, search })
+        .collect();
+    Ok(Some((channel_id, songs)))
+}
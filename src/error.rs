@@ -0,0 +1,190 @@
+use poise::{serenity_prelude as serenity, CreateReply, FrameworkError};
+
+use crate::{commands::help, hooks, Context, Data, Error};
+
+const ERROR_COLOUR: (u8, u8, u8) = (237, 66, 69);
+const WARNING_COLOUR: (u8, u8, u8) = (254, 231, 92);
+
+/// Handles every error from the framework, explaining what went wrong to the user
+///
+/// Errors that aren't the user's fault are logged with a correlation ID, which is also shown to the
+/// user and sent to the `error_channel` if one is configured, so a report can be matched to the logs
+pub async fn on_error(error: FrameworkError<'_, Data, Error>) {
+    let result = match error {
+        FrameworkError::Command { error, ctx, .. } => internal_error(ctx, &error.to_string()).await,
+        FrameworkError::CommandPanic { payload, ctx, .. } => {
+            let payload = payload.as_deref().unwrap_or("no panic message");
+            internal_error(ctx, &format!("panicked: {}", payload)).await
+        }
+        FrameworkError::CommandCheckFailed {
+            error: Some(error),
+            ctx,
+            ..
+        } => internal_error(ctx, &format!("a check failed: {}", error)).await,
+        // The check has already told the user why it failed
+        FrameworkError::CommandCheckFailed { error: None, .. } => Ok(()),
+        FrameworkError::ArgumentParse {
+            error, input, ctx, ..
+        } => {
+            let problem = match input {
+                Some(input) => format!("Couldn't understand `{}`: {}", input, error),
+                None => error.to_string(),
+            };
+            let usage = format!("Usage: `{}{}`", ctx.prefix(), help::usage(ctx.command()));
+            warn(ctx, "Invalid arguments", &format!("{}\n{}", problem, usage)).await
+        }
+        FrameworkError::SubcommandRequired { ctx } => {
+            let subcommands: Vec<_> = ctx
+                .command()
+                .subcommands
+                .iter()
+                .map(|c| format!("`{}`", c.name))
+                .collect();
+            let description = format!("Pick one of: {}", subcommands.join(", "));
+            warn(ctx, "Missing subcommand", &description).await
+        }
+        FrameworkError::CooldownHit {
+            remaining_cooldown,
+            ctx,
+            ..
+        } => {
+            let description = format!(
+                "Try again in {:.1} seconds",
+                remaining_cooldown.as_secs_f32()
+            );
+            warn(ctx, "Slow down", &description).await
+        }
+        FrameworkError::MissingUserPermissions {
+            missing_permissions,
+            ctx,
+            ..
+        } => {
+            let description = match missing_permissions {
+                Some(permissions) => format!("You need these permissions: {}", permissions),
+                None => {
+                    "Your permissions couldn't be checked, so the command wasn't run".to_string()
+                }
+            };
+            warn(ctx, "Missing permissions", &description).await
+        }
+        FrameworkError::MissingBotPermissions {
+            missing_permissions,
+            ctx,
+            ..
+        } => {
+            let description = format!("I need these permissions: {}", missing_permissions);
+            warn(ctx, "Missing permissions", &description).await
+        }
+        FrameworkError::NotAnOwner { ctx, .. } => {
+            warn(
+                ctx,
+                "Not allowed",
+                "Only the bot's owners can use this command",
+            )
+            .await
+        }
+        FrameworkError::GuildOnly { ctx, .. } => {
+            warn(
+                ctx,
+                "Server only",
+                "This command can only be used in a server",
+            )
+            .await
+        }
+        FrameworkError::DmOnly { ctx, .. } => {
+            warn(ctx, "DM only", "This command can only be used in DMs").await
+        }
+        FrameworkError::NsfwOnly { ctx, .. } => {
+            warn(
+                ctx,
+                "NSFW only",
+                "This command can only be used in NSFW channels",
+            )
+            .await
+        }
+        // Everything else has no one to reply to, so poise's logging is enough
+        error => poise::builtins::on_error(error).await,
+    };
+
+    if let Err(e) = result {
+        tracing::error!("failed to report an error: {}", e);
+    }
+}
+
+/// Logs an unexpected error and tells the user something went wrong, without the details
+async fn internal_error(ctx: Context<'_>, error: &str) -> Result<(), serenity::Error> {
+    let id = correlation_id();
+    let command = ctx.command().qualified_name.clone();
+    hooks::span(ctx).await.in_scope(|| {
+        tracing::error!(
+            correlation_id = %id,
+            command = %command,
+            user = %ctx.author().id,
+            guild = ?ctx.guild_id(),
+            "command failed: {}",
+            error
+        )
+    });
+    hooks::record_usage(ctx, true).await;
+
+    let embed = serenity::CreateEmbed::default()
+        .title("Something went wrong")
+        .description("The command couldn't be completed, please try again later")
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Error ID: {}",
+            id
+        )))
+        .colour(ERROR_COLOUR);
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    let error_channel = ctx.data().config.read().unwrap().error_channel;
+    if let Some(channel) = error_channel {
+        let location = match ctx.guild_id() {
+            Some(guild_id) => format!("in guild {}", guild_id),
+            None => "in DMs".to_string(),
+        };
+        let embed = serenity::CreateEmbed::default()
+            .title(format!("Error in `{}`", command))
+            .description(format!("```\n{}\n```", truncate(error, 4000)))
+            .field("User", ctx.author().id.to_string(), true)
+            .field("Where", location, true)
+            .footer(serenity::CreateEmbedFooter::new(format!(
+                "Error ID: {}",
+                id
+            )))
+            .colour(ERROR_COLOUR);
+        if let Err(e) = channel
+            .send_message(ctx, serenity::CreateMessage::new().embed(embed))
+            .await
+        {
+            tracing::warn!(correlation_id = %id, "failed to send the error to the error channel: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Tells the user they made a mistake
+async fn warn(ctx: Context<'_>, title: &str, description: &str) -> Result<(), serenity::Error> {
+    let embed = serenity::CreateEmbed::default()
+        .title(title)
+        .description(description)
+        .colour(WARNING_COLOUR);
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// A short random ID to match what the user sees with the logs
+fn correlation_id() -> String {
+    format!("{:08x}", rand::random::<u32>())
+}
+
+/// Cuts the text to at most `max` characters, without splitting one
+pub fn truncate(text: &str, max: usize) -> &str {
+    match text.char_indices().nth(max) {
+        Some((i, _)) => &text[..i],
+        None => text,
+    }
+}
@@ -0,0 +1,38 @@
+use poise::serenity_prelude as serenity;
+
+#[cfg(feature = "voice")]
+use crate::commands::voice::radio;
+#[cfg(all(feature = "voice", feature = "database"))]
+use crate::shutdown;
+use crate::{Data, Error};
+
+/// Handles gateway events that aren't commands
+#[cfg_attr(not(feature = "voice"), allow(unused_variables))]
+pub async fn event_handler(
+    ctx: &serenity::Context,
+    event: &serenity::FullEvent,
+    _framework: poise::FrameworkContext<'_, Data, Error>,
+    data: &Data,
+) -> Result<(), Error> {
+    match event {
+        // Voice connections don't survive a new gateway session, so bring the radios back
+        #[cfg(feature = "voice")]
+        serenity::FullEvent::Ready { .. } | serenity::FullEvent::Resume { .. } => {
+            let manager = songbird::get(ctx).await.unwrap().clone();
+            tokio::spawn(radio::ensure_connected(
+                manager.clone(),
+                data.http.clone(),
+                data.radio.clone(),
+            ));
+
+            // Queues are only saved on shutdown, so there's nothing to restore after a resume
+            #[cfg(feature = "database")]
+            if let serenity::FullEvent::Ready { data_about_bot } = event {
+                let guilds = data_about_bot.guilds.iter().map(|guild| guild.id).collect();
+                tokio::spawn(shutdown::restore_queues(manager, data.http.clone(), guilds));
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
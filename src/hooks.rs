@@ -0,0 +1,160 @@
+use std::{collections::HashMap, sync::OnceLock, time::Instant};
+
+use poise::{BoxFuture, FrameworkError};
+use tracing::Instrument;
+
+#[cfg(feature = "database")]
+use crate::db::command_usage;
+use crate::{checks, cooldowns, Context, Data, Error};
+
+type PrefixAction = for<'a> fn(
+    poise::PrefixContext<'a, Data, Error>,
+) -> BoxFuture<'a, Result<(), FrameworkError<'a, Data, Error>>>;
+type SlashAction = for<'a> fn(
+    poise::ApplicationContext<'a, Data, Error>,
+) -> BoxFuture<'a, Result<(), FrameworkError<'a, Data, Error>>>;
+
+/// The actions of commands by qualified name
+type Actions = HashMap<String, (Option<PrefixAction>, Option<SlashAction>)>;
+
+/// The actions of every command, from before [`instrument`] replaced them
+static ACTIONS: OnceLock<Actions> = OnceLock::new();
+
+/// Details about a command invocation, stored in its invocation data by [`pre_command`]
+pub struct Invocation {
+    pub span: tracing::Span,
+    pub start: Instant,
+}
+
+/// Starts the span that the command's logs are recorded in, and the command's cooldowns
+pub async fn pre_command(ctx: Context<'_>) {
+    let span = tracing::info_span!(
+        "command",
+        command = %ctx.command().qualified_name,
+        guild = ?ctx.guild_id().map(|id| id.get()),
+        channel = %ctx.channel_id(),
+        user = %ctx.author().id,
+    );
+    span.in_scope(|| tracing::info!("command started"));
+
+    if !checks::is_developer(ctx) {
+        cooldowns::start(ctx);
+    }
+
+    ctx.set_invocation_data(Invocation {
+        span,
+        start: Instant::now(),
+    })
+    .await;
+}
+
+/// Logs that the command finished and records it in the usage stats
+pub async fn post_command(ctx: Context<'_>) {
+    record_usage(ctx, false).await;
+}
+
+/// Records a command that was run in the usage stats, doing nothing if it never started
+pub async fn record_usage(ctx: Context<'_>, error: bool) {
+    let Some((span, latency_ms)) = ctx.invocation_data::<Invocation>().await.map(|invocation| {
+        (
+            invocation.span.clone(),
+            invocation.start.elapsed().as_millis() as u64,
+        )
+    }) else {
+        return;
+    };
+
+    if !error {
+        span.in_scope(|| tracing::info!(duration_ms = latency_ms, "command finished"));
+    }
+
+    let command = &ctx.command().qualified_name;
+    ctx.data().metrics.record_command(command, error);
+    #[cfg(feature = "database")]
+    if let Err(e) =
+        command_usage::record(command, ctx.guild_id(), ctx.author().id, latency_ms, error).await
+    {
+        span.in_scope(|| tracing::warn!("failed to record command usage: {}", e));
+    }
+}
+
+/// The span of the command being run, or a disabled one if it hasn't started yet
+pub async fn span(ctx: Context<'_>) -> tracing::Span {
+    ctx.invocation_data::<Invocation>()
+        .await
+        .map_or_else(tracing::Span::none, |invocation| invocation.span.clone())
+}
+
+/// Makes every command run inside the span [`pre_command`] starts, so logs from within a command
+/// carry its details too
+///
+/// Poise's actions are plain function pointers, so the originals are kept in [`ACTIONS`] and looked
+/// up by the wrappers
+pub fn instrument(commands: &mut [poise::Command<Data, Error>]) {
+    // The framework does this as well, but the names are needed now to look the actions up by
+    poise::framework::set_qualified_names(commands);
+    let mut actions = HashMap::new();
+    instrument_each(commands, &mut actions);
+    // Every call is given the same commands, so whichever got in first is as good as this one
+    let _ = ACTIONS.set(actions);
+}
+
+fn instrument_each(commands: &mut [poise::Command<Data, Error>], actions: &mut Actions) {
+    for command in commands {
+        actions.insert(
+            command.qualified_name.clone(),
+            (command.prefix_action, command.slash_action),
+        );
+        if command.prefix_action.is_some() {
+            command.prefix_action = Some(|ctx| Box::pin(run_prefix(ctx)));
+        }
+        if command.slash_action.is_some() {
+            command.slash_action = Some(|ctx| Box::pin(run_slash(ctx)));
+        }
+        instrument_each(&mut command.subcommands, actions);
+    }
+}
+
+async fn run_prefix(
+    ctx: poise::PrefixContext<'_, Data, Error>,
+) -> Result<(), FrameworkError<'_, Data, Error>> {
+    let action = ACTIONS
+        .get()
+        .and_then(|actions| actions.get(&ctx.command.qualified_name)?.0)
+        .expect("prefix command without its original action");
+    let span = span(poise::Context::Prefix(ctx)).await;
+    action(ctx).instrument(span).await
+}
+
+async fn run_slash(
+    ctx: poise::ApplicationContext<'_, Data, Error>,
+) -> Result<(), FrameworkError<'_, Data, Error>> {
+    let action = ACTIONS
+        .get()
+        .and_then(|actions| actions.get(&ctx.command.qualified_name)?.1)
+        .expect("slash command without its original action");
+    let span = span(poise::Context::Application(ctx)).await;
+    action(ctx).instrument(span).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_action_of_every_command() {
+        fn walk(commands: &[poise::Command<Data, Error>], actions: &Actions) {
+            for command in commands {
+                let (prefix, slash) = actions
+                    .get(&command.qualified_name)
+                    .unwrap_or_else(|| panic!("no actions kept for {}", command.qualified_name));
+                assert_eq!(prefix.is_some(), command.prefix_action.is_some());
+                assert_eq!(slash.is_some(), command.slash_action.is_some());
+                walk(&command.subcommands, actions);
+            }
+        }
+
+        let commands = crate::commands::all();
+        walk(&commands, ACTIONS.get().unwrap());
+    }
+}
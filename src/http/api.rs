@@ -0,0 +1,172 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use poise::serenity_prelude as serenity;
+use serde_json::{json, Value};
+
+use super::HttpState;
+use crate::config;
+#[cfg(feature = "database")]
+use crate::db;
+
+/// Whether the process is up, for liveness probes
+pub async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// Whether the bot can serve commands, meaning a shard is connected and the database responds
+///
+/// This is a 503 until the first shard has connected
+pub async fn readyz(State(state): State<HttpState>) -> Response {
+    let gateway = state
+        .shard_manager
+        .runners
+        .lock()
+        .await
+        .values()
+        .any(|runner| runner.stage == serenity::ConnectionStage::Connected);
+    #[cfg(feature = "database")]
+    let database = sqlx::query("SELECT 1")
+        .execute(db::get_database())
+        .await
+        .is_ok();
+    // There's no database to be unreachable
+    #[cfg(not(feature = "database"))]
+    let database = true;
+
+    let status = if gateway && database {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(json!({ "gateway": gateway, "database": database })),
+    )
+        .into_response()
+}
+
+/// Rejects requests without the configured `api_token` as a bearer token
+///
+/// The token is read on every request, so a reload takes effect immediately
+pub async fn authenticate(
+    State(state): State<HttpState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let expected = state
+        .config
+        .read()
+        .unwrap()
+        .api_token
+        .as_ref()
+        .map(|token| token.expose().clone());
+    let Some(expected) = expected else {
+        return (StatusCode::FORBIDDEN, "no api_token is configured").into_response();
+    };
+
+    let given = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match given {
+        Some(given) if constant_time_eq(given.as_bytes(), expected.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "missing or incorrect token",
+        )
+            .into_response(),
+    }
+}
+
+/// Every guild the bot is in, which is none until the guilds arrive from the gateway
+pub async fn guilds(State(state): State<HttpState>) -> Json<Value> {
+    let guilds: Vec<_> = state
+        .cache
+        .guilds()
+        .into_iter()
+        .filter_map(|guild_id| {
+            let guild = state.cache.guild(guild_id)?;
+            Some(json!({
+                "id": guild.id.to_string(),
+                "name": guild.name,
+                "members": guild.member_count,
+            }))
+        })
+        .collect();
+    Json(json!(guilds))
+}
+
+/// The voice connection and queue of every guild the bot has a call in
+#[cfg(feature = "voice")]
+pub async fn queues(State(state): State<HttpState>) -> Json<Value> {
+    let mut queues = Vec::new();
+    for (guild_id, call) in state.songbird.iter() {
+        let (channel, handles) = {
+            let call = call.lock().await;
+            let channel = call
+                .current_connection()
+                .and_then(|connection| connection.channel_id)
+                .map(|channel| channel.0.to_string());
+            (channel, call.queue().current_queue())
+        };
+        let radio = state
+            .radio
+            .read()
+            .unwrap()
+            .contains_key(&serenity::GuildId::new(guild_id.0.get()));
+
+        let mut tracks = Vec::new();
+        for handle in handles {
+            let track = match handle.get_info().await {
+                Ok(info) => json!({
+                    "id": handle.uuid().to_string(),
+                    "playing": matches!(info.playing, songbird::tracks::PlayMode::Play),
+                    "position_secs": info.position.as_secs(),
+                }),
+                // The track has already ended
+                Err(_) => continue,
+            };
+            tracks.push(track);
+        }
+
+        queues.push(json!({
+            "guild": guild_id.0.to_string(),
+            "channel": channel,
+            "radio": radio,
+            "tracks": tracks,
+        }));
+    }
+    Json(json!(queues))
+}
+
+/// Reloads the config file, the same as the `reload` command
+pub async fn reload(State(state): State<HttpState>) -> Response {
+    match config::reload(&state.config, &state.config_args) {
+        Ok(changes) => {
+            tracing::info!("config reloaded through the API");
+            Json(json!({ "changes": changes })).into_response()
+        }
+        Err(e) => {
+            tracing::warn!("failed to reload the config through the API: {}", e);
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Compares the tokens without returning early, so their contents can't be found through timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,143 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+};
+
+use axum::{extract::State, http::header, response::IntoResponse};
+
+use super::HttpState;
+
+/// Counters that are updated as commands are run, read whenever the metrics are scraped
+#[derive(Default)]
+pub struct Metrics {
+    commands: Mutex<BTreeMap<String, CommandCounts>>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct CommandCounts {
+    executed: u64,
+    errors: u64,
+}
+
+impl Metrics {
+    pub fn record_command(&self, command: &str, error: bool) {
+        let mut commands = self.commands.lock().unwrap();
+        let counts = commands.entry(command.to_string()).or_default();
+        counts.executed += 1;
+        if error {
+            counts.errors += 1;
+        }
+    }
+}
+
+pub type SharedMetrics = Arc<Metrics>;
+
+/// Renders the metrics in the Prometheus text format
+pub async fn metrics(State(state): State<HttpState>) -> impl IntoResponse {
+    let mut out = String::new();
+
+    let commands = state.metrics.commands.lock().unwrap().clone();
+    write_help(
+        &mut out,
+        "bot697_commands_executed_total",
+        "counter",
+        "Commands run",
+    );
+    for (command, counts) in &commands {
+        writeln!(
+            out,
+            "bot697_commands_executed_total{{command=\"{}\"}} {}",
+            escape(command),
+            counts.executed
+        )
+        .unwrap();
+    }
+    write_help(
+        &mut out,
+        "bot697_command_errors_total",
+        "counter",
+        "Commands that failed",
+    );
+    for (command, counts) in &commands {
+        writeln!(
+            out,
+            "bot697_command_errors_total{{command=\"{}\"}} {}",
+            escape(command),
+            counts.errors
+        )
+        .unwrap();
+    }
+
+    write_help(&mut out, "bot697_guilds", "gauge", "Guilds the bot is in");
+    writeln!(out, "bot697_guilds {}", state.cache.guild_count()).unwrap();
+
+    write_help(
+        &mut out,
+        "bot697_gateway_latency_seconds",
+        "gauge",
+        "Time for the gateway to acknowledge a heartbeat, per shard",
+    );
+    for (shard_id, runner) in state.shard_manager.runners.lock().await.iter() {
+        if let Some(latency) = runner.latency {
+            writeln!(
+                out,
+                "bot697_gateway_latency_seconds{{shard=\"{}\"}} {}",
+                shard_id,
+                latency.as_secs_f64()
+            )
+            .unwrap();
+        }
+    }
+
+    #[cfg(feature = "voice")]
+    {
+        let mut connections = 0;
+        let mut queues = Vec::new();
+        for (guild_id, call) in state.songbird.iter() {
+            let call = call.lock().await;
+            if call.current_connection().is_some() {
+                connections += 1;
+            }
+            queues.push((guild_id, call.queue().len()));
+        }
+
+        write_help(
+            &mut out,
+            "bot697_voice_connections",
+            "gauge",
+            "Active voice connections",
+        );
+        writeln!(out, "bot697_voice_connections {}", connections).unwrap();
+
+        write_help(
+            &mut out,
+            "bot697_queue_length",
+            "gauge",
+            "Tracks queued in a guild, including the one playing",
+        );
+        for (guild_id, length) in queues {
+            writeln!(
+                out,
+                "bot697_queue_length{{guild=\"{}\"}} {}",
+                guild_id, length
+            )
+            .unwrap();
+        }
+    }
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], out)
+}
+
+fn write_help(out: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+}
+
+/// Escapes a label value, which can't contain unescaped quotes, backslashes or newlines
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
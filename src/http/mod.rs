@@ -0,0 +1,84 @@
+use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
+
+use axum::{
+    middleware,
+    routing::{get, post},
+    Router,
+};
+use poise::serenity_prelude as serenity;
+
+#[cfg(feature = "voice")]
+use crate::commands::voice::radio::RadioGuilds;
+use crate::{cli::ConfigArgs, config::SharedConfig};
+
+pub mod api;
+pub mod metrics;
+
+/// What the HTTP handlers can read from the running bot
+///
+/// This is all available before the client connects, so the cache is empty and no shards are running
+/// until then
+#[derive(Clone)]
+pub struct HttpState {
+    pub cache: Arc<serenity::Cache>,
+    pub shard_manager: Arc<serenity::ShardManager>,
+    #[cfg(feature = "voice")]
+    pub songbird: Arc<songbird::Songbird>,
+    pub metrics: metrics::SharedMetrics,
+    pub config: SharedConfig,
+    pub config_args: ConfigArgs,
+    #[cfg(feature = "voice")]
+    pub radio: RadioGuilds,
+}
+
+/// Listens on the addresses the metrics and the API are configured with, which can be the same, and
+/// serves them in the background
+///
+/// This runs before the client starts, so probes get an answer while the shards are connecting
+pub async fn start(
+    metrics_address: Option<SocketAddr>,
+    api_address: Option<SocketAddr>,
+    state: HttpState,
+) {
+    let mut routers: BTreeMap<SocketAddr, Router<HttpState>> = BTreeMap::new();
+
+    if let Some(address) = metrics_address {
+        let router = routers.entry(address).or_default();
+        *router = std::mem::take(router).route("/metrics", get(metrics::metrics));
+    }
+    if let Some(address) = api_address {
+        let admin = Router::new()
+            .route("/guilds", get(api::guilds))
+            .route("/reload", post(api::reload));
+        #[cfg(feature = "voice")]
+        let admin = admin.route("/queues", get(api::queues));
+        let admin = admin.route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            api::authenticate,
+        ));
+
+        let router = routers.entry(address).or_default();
+        *router = std::mem::take(router)
+            .route("/healthz", get(api::healthz))
+            .route("/readyz", get(api::readyz))
+            .nest("/admin", admin);
+    }
+
+    for (address, router) in routers {
+        let listener = match tokio::net::TcpListener::bind(address).await {
+            Ok(l) => l,
+            Err(e) => {
+                tracing::error!("failed to listen on {}: {}", address, e);
+                continue;
+            }
+        };
+        tracing::info!("serving HTTP on {}", address);
+        tokio::spawn(serve(address, listener, router.with_state(state.clone())));
+    }
+}
+
+async fn serve(address: SocketAddr, listener: tokio::net::TcpListener, app: Router) {
+    if let Err(e) = axum::serve(listener, app).await {
+        tracing::error!("the HTTP server on {} stopped: {}", address, e);
+    }
+}
@@ -0,0 +1,192 @@
+use std::io::Write;
+
+use poise::serenity_prelude as serenity;
+
+use crate::{
+    config::{CommandRegistration, Config, ConfigFormat, RadioConfig},
+    secret::{self, Secret},
+};
+
+/// Descriptions written above each setting in the starter config
+const FIELD_DOCS: &[(&str, &str)] = &[
+    (
+        "token",
+        "The bot's token. ${VAR} is replaced with the environment variable VAR",
+    ),
+    (
+        "database_url",
+        "Where the SQLite database is stored, mode=rwc creates it if it doesn't exist",
+    ),
+    ("developers", "IDs of users that can run developer commands"),
+    (
+        "developer_guilds",
+        "IDs of guilds that developer commands can be tested in",
+    ),
+    (
+        "prefixes",
+        "Prefixes for commands, the first is the main one",
+    ),
+    ("mention_as_prefix", "Count mentions of the bot as a prefix"),
+    (
+        "allow_self_messages",
+        "Let the bot trigger commands from its own messages, needs allow_bot_messages",
+    ),
+    ("allow_bot_messages", "Let other bots trigger commands"),
+    ("case_sensitive", "Make command names case sensitive"),
+    (
+        "command_registration",
+        "Where slash commands are registered: global, developer_guilds or off",
+    ),
+];
+
+/// Writes a starter config to `path`, in the format given by its extension
+pub fn init(path: &str, interactive: bool, force: bool) {
+    if !force && std::path::Path::new(path).exists() {
+        println!("{} already exists, use --force to overwrite it", path);
+        std::process::exit(1);
+    }
+
+    let mut config = Config {
+        // Left out so the other token sources work, as a missing ${DISCORD_TOKEN} would stop the
+        // config from loading
+        database_url: Some(Secret::new("sqlite://bot697.sqlite?mode=rwc".to_string())),
+        developers: Some(Default::default()),
+        developer_guilds: Some(Default::default()),
+        prefixes: Some(vec![",".to_string()]),
+        mention_as_prefix: Some(true),
+        allow_self_messages: Some(false),
+        allow_bot_messages: Some(false),
+        case_sensitive: Some(false),
+        command_registration: Some(CommandRegistration::Global),
+        ..Default::default()
+    };
+
+    if interactive {
+        prompt_values(&mut config);
+    }
+
+    let format = ConfigFormat::from_path(path);
+    // The values were entered by the user to be written to the file, so they shouldn't be redacted
+    let contents = secret::exposed(|| match format {
+        // JSON doesn't support comments, so there's nothing to add
+        ConfigFormat::Json => format.serialize(&config),
+        ConfigFormat::Toml | ConfigFormat::Yaml => commented(format, &config),
+    });
+
+    if let Err(e) = std::fs::write(path, contents) {
+        tracing::error!("failed to write the starter config: {}", e);
+        println!("Failed to write {}: {}", path, e);
+        std::process::exit(1);
+    }
+    println!(
+        "Wrote a starter config to {}, check it with: check-config --config-file {}",
+        path, path
+    );
+}
+
+/// Serializes the config with a comment above each setting, followed by a commented out radio example
+fn commented(format: ConfigFormat, config: &Config) -> String {
+    let mut out = String::from("# Configuration for bot697, generated by the init command\n\n");
+
+    if config.token.is_none() {
+        let token = Config {
+            token: Some(Secret::new("${DISCORD_TOKEN}".to_string())),
+            ..Default::default()
+        };
+        out.push_str(
+            "# The bot's token. ${VAR} is replaced with the environment variable VAR. When left out,\n\
+             # the token comes from DISCORD_TOKEN or the --token options\n",
+        );
+        for line in format.serialize(&token).lines() {
+            out.push_str(&format!("# {}\n", line));
+        }
+    }
+
+    for line in format.serialize(config).lines() {
+        let key = match format {
+            ConfigFormat::Toml => line.split_once(" = ").map(|(key, _)| key),
+            _ => line.split_once(':').map(|(key, _)| key),
+        };
+        let doc = FIELD_DOCS.iter().find(|(field, _)| Some(*field) == key);
+        if let Some((_, doc)) = doc {
+            out.push_str(&format!("# {}\n", doc));
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    let example = Config {
+        radio: Some(vec![RadioConfig {
+            guild: serenity::GuildId::new(1),
+            channel: serenity::ChannelId::new(1),
+            station: Some("https://example.com/stream.mp3".to_string()),
+            playlist: None,
+        }]),
+        ..Default::default()
+    };
+    out.push_str(
+        "\n# Keep the bot playing in a voice channel around the clock, one entry per guild.\n",
+    );
+    out.push_str("# Set playlist to a list of links instead of station to loop songs.\n");
+    for line in format.serialize(&example).lines() {
+        out.push_str(&format!("# {}\n", line));
+    }
+
+    out
+}
+
+/// Asks for the values that differ between deployments, keeping the defaults for empty answers
+fn prompt_values(config: &mut Config) {
+    if let Some(prefix) = prompt("Main prefix", ",") {
+        config.prefixes = Some(vec![prefix]);
+    }
+    if let Some(ids) = prompt("Developer user IDs, separated by commas", "") {
+        config.developers = Some(
+            parse_ids(&ids)
+                .into_iter()
+                .map(serenity::UserId::new)
+                .collect(),
+        );
+    }
+    if let Some(ids) = prompt("Developer guild IDs, separated by commas", "") {
+        config.developer_guilds = Some(
+            parse_ids(&ids)
+                .into_iter()
+                .map(serenity::GuildId::new)
+                .collect(),
+        );
+    }
+    if let Some(var) = prompt(
+        "Environment variable to read the token from, or empty to use DISCORD_TOKEN or the --token options",
+        "",
+    ) {
+        config.token = Some(Secret::new(format!("${{{}}}", var)));
+    }
+    if let Some(url) = prompt("Database URL", "sqlite://bot697.sqlite?mode=rwc") {
+        config.database_url = Some(Secret::new(url));
+    }
+}
+
+fn prompt(question: &str, default: &str) -> Option<String> {
+    print!("{} [{}]: ", question, default);
+    std::io::stdout().flush().ok()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).ok()?;
+    let answer = answer.trim();
+    (!answer.is_empty()).then(|| answer.to_string())
+}
+
+fn parse_ids(ids: &str) -> Vec<u64> {
+    ids.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .filter_map(|id| match id.parse() {
+            Ok(id) => Some(id),
+            Err(e) => {
+                println!("Skipping {:?} as it isn't a valid ID: {}", id, e);
+                None
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,100 @@
+use poise::serenity_prelude as serenity;
+
+use crate::{config::Config, Data, Error};
+
+/// Commands outside the voice category that look at who's in a voice channel
+const VOICE_STATE_COMMANDS: &[&str] = &["teams"];
+
+/// An intent that something the bot does relies on, and what that is
+struct Requirement {
+    intents: serenity::GatewayIntents,
+    reason: &'static str,
+}
+
+/// The gateway intents to connect with, either from the config or only those the commands need
+///
+/// Warns about anything that won't work without an intent that's missing from the config's list
+pub fn select(
+    config: &Config,
+    commands: &[poise::Command<Data, Error>],
+) -> serenity::GatewayIntents {
+    let requirements = requirements(config, commands);
+
+    let intents = match &config.intents {
+        Some(names) => {
+            // Unknown names are reported when the config is validated
+            let intents = names
+                .iter()
+                .filter_map(|name| parse(name))
+                .fold(serenity::GatewayIntents::empty(), |all, intent| {
+                    all | intent
+                });
+            for requirement in &requirements {
+                let missing = requirement.intents - intents;
+                if !missing.is_empty() {
+                    tracing::warn!(
+                        "{} needs the {} intent(s), which the config doesn't enable",
+                        requirement.reason,
+                        names_of(missing).join(", ")
+                    );
+                }
+            }
+            intents
+        }
+        None => requirements
+            .iter()
+            .fold(serenity::GatewayIntents::empty(), |all, r| all | r.intents),
+    };
+
+    let privileged = intents & serenity::GatewayIntents::privileged();
+    if !privileged.is_empty() {
+        tracing::info!(
+            "using the privileged intent(s) {}, which must be enabled in the developer portal",
+            names_of(privileged).join(", ")
+        );
+    }
+    tracing::debug!("gateway intents: {}", names_of(intents).join(", "));
+
+    intents
+}
+
+/// Parses an intent from its name, e.g. `message_content`, ignoring case
+pub fn parse(name: &str) -> Option<serenity::GatewayIntents> {
+    serenity::GatewayIntents::from_name(&name.trim().to_uppercase())
+}
+
+fn requirements(config: &Config, commands: &[poise::Command<Data, Error>]) -> Vec<Requirement> {
+    let mut requirements = vec![Requirement {
+        intents: serenity::GatewayIntents::GUILDS,
+        reason: "caching guilds and channels",
+    }];
+
+    let has_prefix_commands = commands.iter().any(|c| c.prefix_action.is_some());
+    if has_prefix_commands {
+        requirements.push(Requirement {
+            intents: serenity::GatewayIntents::GUILD_MESSAGES
+                | serenity::GatewayIntents::DIRECT_MESSAGES
+                | serenity::GatewayIntents::MESSAGE_CONTENT,
+            reason: "prefix commands",
+        });
+    }
+
+    let has_voice_commands = commands.iter().any(|c| {
+        c.category.as_deref() == Some("voice") || VOICE_STATE_COMMANDS.contains(&c.name.as_str())
+    });
+    if has_voice_commands || config.radio.as_ref().is_some_and(|r| !r.is_empty()) {
+        requirements.push(Requirement {
+            intents: serenity::GatewayIntents::GUILD_VOICE_STATES,
+            reason: "voice commands, teams and the radio",
+        });
+    }
+
+    requirements
+}
+
+fn names_of(intents: serenity::GatewayIntents) -> Vec<String> {
+    intents
+        .iter_names()
+        .map(|(name, _)| name.to_lowercase())
+        .collect()
+}
@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    fmt::{self, MakeWriter},
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+    EnvFilter, Layer, Registry,
+};
+
+use crate::cli::{LogFormat, LogOptions, LogRotation};
+
+/// Sets up logging to stdout, and to a file if one was given
+///
+/// The returned guard flushes the file when it's dropped, so it needs to be kept until the bot exits
+pub fn init(options: &LogOptions) -> Option<WorkerGuard> {
+    let filter = match &options.log_level {
+        Some(level) => EnvFilter::try_new(level).unwrap_or_else(|e| {
+            println!("Invalid --log-level {:?}: {}\nExiting", level, e);
+            std::process::exit(1);
+        }),
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+    };
+
+    let mut layers = vec![layer(options.log_format, std::io::stdout, true)];
+    let guard = match &options.log_file {
+        Some(path) => {
+            let path = Path::new(path);
+            let directory = path
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+            let name = path.file_name().unwrap_or(path.as_os_str());
+            let rotation = match options.log_rotation {
+                LogRotation::Minutely => Rotation::MINUTELY,
+                LogRotation::Hourly => Rotation::HOURLY,
+                LogRotation::Daily => Rotation::DAILY,
+                LogRotation::Never => Rotation::NEVER,
+            };
+            let appender = RollingFileAppender::builder()
+                .rotation(rotation)
+                .filename_prefix(name.to_string_lossy())
+                .build(directory)
+                .unwrap_or_else(|e| {
+                    println!("Can't write logs to {:?}: {}\nExiting", path, e);
+                    std::process::exit(1);
+                });
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            layers.push(layer(options.log_format, writer, false));
+            Some(guard)
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(layers)
+        .with(filter)
+        .init();
+
+    guard
+}
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+fn layer<W>(format: LogFormat, writer: W, ansi: bool) -> BoxedLayer
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let layer = fmt::layer().with_writer(writer).with_ansi(ansi);
+    match format {
+        LogFormat::Full => layer.boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Pretty => layer.pretty().boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
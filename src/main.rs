@@ -1,40 +1,71 @@
+mod checks;
 mod cli;
 mod commands;
+mod config;
+mod cooldowns;
+#[cfg(feature = "database")]
 mod db;
-
-use std::collections::HashSet;
+mod error;
+mod events;
+mod hooks;
+mod http;
+mod init;
+mod intents;
+mod logging;
+mod registration;
+mod secret;
+mod shutdown;
+
+use std::sync::{Arc, RwLock};
 
 use anyhow::Result;
 use clap::Parser;
-use cli::{Cli, Commands, TokenOptions};
-use poise::{serenity_prelude as serenity, PrefixFrameworkOptions};
+use cli::{Cli, Commands, ConfigArgs, ShardCount, ShardOptions, TokenOptions};
+use config::{CommandRegistration, Config, ConfigError, SharedConfig};
+use poise::{serenity_prelude as serenity, BoxFuture, PrefixFrameworkOptions};
+#[cfg(feature = "voice")]
 use songbird::SerenityInit;
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, Default)]
-struct Config {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    developers: Option<HashSet<serenity::UserId>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    developer_guilds: Option<HashSet<serenity::GuildId>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    prefixes: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    mention_as_prefix: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    allow_self_messages: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    allow_bot_messages: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    case_sensitive: Option<bool>,
-}
-
 // User data, which is stored and accessible in all command invocations
 struct Data {
-    developers: HashSet<serenity::UserId>,
-    developer_guilds: HashSet<serenity::GuildId>,
-    config: Config,
+    config: SharedConfig,
+    /// The CLI arguments the config was loaded with, reapplied whenever it's reloaded
+    #[cfg(feature = "dev")]
+    config_args: ConfigArgs,
+    #[cfg(feature = "utils")]
     start_time: std::time::Instant,
+    #[cfg(feature = "voice")]
     http: reqwest::Client,
+    /// Guilds in radio mode, which should never be disconnected for being idle
+    #[cfg(feature = "voice")]
+    radio: commands::voice::radio::RadioGuilds,
+    /// Where the rng commands get their random numbers from
+    #[cfg(feature = "rng")]
+    rng: commands::rng::provider::RngProvider,
+    metrics: http::metrics::SharedMetrics,
+    #[cfg(feature = "dev")]
+    shutdown: shutdown::Shutdown,
+}
+
+impl Data {
+    fn is_developer(&self, user_id: serenity::UserId) -> bool {
+        self.config
+            .read()
+            .unwrap()
+            .developers
+            .as_ref()
+            .is_some_and(|ids| ids.contains(&user_id))
+    }
+
+    #[cfg(feature = "dev")]
+    fn is_developer_guild(&self, guild_id: serenity::GuildId) -> bool {
+        self.config
+            .read()
+            .unwrap()
+            .developer_guilds
+            .as_ref()
+            .is_some_and(|ids| ids.contains(&guild_id))
+    }
 }
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -42,158 +73,412 @@ type Context<'a> = poise::Context<'a, Data, Error>;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let subscriber = tracing_subscriber::FmtSubscriber::new();
-    tracing::subscriber::set_global_default(subscriber)
-        .expect("this should be the only call of set_global_default");
-
-    let db = sqlx::sqlite::SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect_with(std::env::var("DATABASE_URL").unwrap().parse().unwrap())
-        .await;
-    let Ok(database) = db else {
-        tracing::error!("exiting as a connection to the database could not be created");
-        println!("Failed to connect to the database. Exiting");
-        std::process::exit(1);
-    };
-    db::set_database(database);
-
     let args = Cli::parse();
+    let _log_guard = logging::init(&args.log);
 
     match args.command {
         Commands::Run {
             token,
-            prefix,
-            extra_prefix,
-            mention_as_prefix,
-            allow_self_messages,
-            allow_bot_messages,
-            case_sensitive,
-            config_file,
-            developer_id,
-            developer_guild,
+            config,
+            shards,
         } => {
-            let token = get_token(token);
-            let token = match token {
-                Ok(t) => t,
-                Err(_) => {
-                    tracing::error!("exiting as a token could not be read");
-                    println!("Failed to find a token.\
-                        \nEither set the environment variable DISCORD_TOKEN or use one of the following arguments:\
-                        \n\t--token to directly read the token\
-                        \n\t--token_var to read a different environment variable\
-                        \n\t--token_file to read the token from a file\
-                        \nTerminating");
-                    std::process::exit(1);
-                }
-            };
-
-            let mut config: Config = match config_file {
-                Some(f) => match std::fs::read_to_string(f) {
-                    Err(e) => {
-                        tracing::error!("failed to read config file: {}", e);
-                        println!("Failed to read the config file. Exiting");
-                        std::process::exit(1);
-                    }
-                    Ok(s) => match serde_json::from_str(&s) {
-                        Err(e) => {
-                            tracing::error!("failed to parse config file: {}", e);
-                            println!("Failed to parse the config file. Exiting");
-                            std::process::exit(1);
-                        }
-                        Ok(c) => c,
-                    },
-                },
-                None => Config::default(),
-            };
-
-            merge_config_cli_args(
-                &mut config,
-                prefix,
-                extra_prefix,
-                mention_as_prefix,
-                allow_bot_messages,
-                case_sensitive,
-                allow_self_messages,
-                developer_id,
-                developer_guild,
-            );
+            check_shards(shards);
+            let config_args = config;
+            let config = load_config(&config_args);
+            let token = load_token(token, &config);
+
+            #[cfg(feature = "database")]
+            connect_database(&config).await;
 
             let prefix_options = handle_prefixes(&config);
-            run(token, prefix_options, &config).await;
+            run(token, prefix_options, config, config_args, shards).await;
+        }
+        Commands::CheckConfig { config } => check_config(&config),
+        Commands::Register {
+            token,
+            config,
+            mode,
+            force,
+        } => {
+            let config = load_config(&config);
+            let token = load_token(token, &config);
+            register(token, &config, mode, force).await;
         }
+        Commands::Init {
+            path,
+            interactive,
+            force,
+        } => init::init(&path, interactive, force),
     }
 
     Ok(())
 }
 
-async fn run(token: String, prefix_options: PrefixFrameworkOptions<Data, Error>, config: &Config) {
-    let intents = serenity::GatewayIntents::all();
+/// Loads the config, exiting with an explanation if it can't be used
+fn load_config(config_args: &ConfigArgs) -> Config {
+    match config::load(config_args) {
+        Ok(c) => c,
+        Err(ConfigError::Read(e)) => {
+            tracing::error!("failed to read config file: {}", e);
+            println!("Failed to read the config file. Exiting");
+            std::process::exit(1);
+        }
+        Err(e @ (ConfigError::Parse { .. } | ConfigError::MissingEnv { .. })) => {
+            tracing::error!("{}", e);
+            println!("Failed to load the config file: {}\nExiting", e);
+            std::process::exit(1);
+        }
+        Err(ConfigError::Invalid(problems)) => {
+            tracing::error!("exiting as the config is invalid");
+            println!("The config is invalid:");
+            for problem in problems {
+                println!("\t{}", problem);
+            }
+            println!("Run check-config for more details.\nTerminating");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Reads the token, exiting with an explanation if there isn't one
+fn load_token(options: TokenOptions, config: &Config) -> String {
+    match get_token(options, config.token.as_ref().map(|t| t.expose().clone())) {
+        Ok(t) => t,
+        Err(_) => {
+            tracing::error!("exiting as a token could not be read");
+            println!("Failed to find a token.\
+                \nEither set the environment variable DISCORD_TOKEN, set the token field in the config file or use one of the following arguments:\
+                \n\t--token to directly read the token\
+                \n\t--token_var to read a different environment variable\
+                \n\t--token_file to read the token from a file\
+                \n\t--token_credential to read the token from a systemd credential\
+                \nTerminating");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Registers the commands without starting the bot
+async fn register(token: String, config: &Config, mode: Option<CommandRegistration>, force: bool) {
+    let http = serenity::Http::new(&token);
+    let application = match http.get_current_application_info().await {
+        Ok(a) => a,
+        Err(e) => {
+            tracing::error!("failed to get the application info: {}", e);
+            println!("Failed to connect to Discord, check the token is correct. Exiting");
+            std::process::exit(1);
+        }
+    };
+    http.set_application_id(application.id);
+
+    let mode = mode.or(config.command_registration).unwrap_or_default();
+    let developer_guilds = config.developer_guilds.clone().unwrap_or_default();
+    if let Err(e) =
+        registration::register(&http, &commands::all(), mode, developer_guilds, force).await
+    {
+        tracing::error!("failed to register commands: {}", e);
+        println!("Failed to register the commands: {}", e);
+        std::process::exit(1);
+    }
+    println!("Commands registered");
+}
 
-    let developer_user_ids = config.developers.clone().unwrap_or_default();
-    let developer_guild_ids = config.developer_guilds.clone().unwrap_or_default();
+async fn run(
+    token: String,
+    prefix_options: PrefixFrameworkOptions<Data, Error>,
+    config: Config,
+    config_args: ConfigArgs,
+    shards: ShardOptions,
+) {
+    let commands = commands::all();
+    let intents = intents::select(&config, &commands);
+    let (metrics_address, api_address) = (config.metrics_address, config.api_address);
+
+    #[cfg(feature = "voice")]
+    let radio = Arc::new(RwLock::new(commands::voice::radio::load(&config).await));
+    #[cfg(not(feature = "voice"))]
+    if config.radio.as_ref().is_some_and(|r| !r.is_empty()) {
+        tracing::warn!("the radio is configured, but the bot was built without the voice feature");
+    }
 
-    let conf = config.clone();
+    let config = Arc::new(RwLock::new(config));
+    let metrics = http::metrics::SharedMetrics::default();
+    // The setup takes these, the originals are for the HTTP server and signal handlers started below
+    let (setup_config, setup_metrics) = (config.clone(), metrics.clone());
+    #[cfg(feature = "dev")]
+    let setup_args = config_args.clone();
+    #[cfg(feature = "voice")]
+    let setup_radio = radio.clone();
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![
-                commands::ping::ping(),
-                commands::dev::register::devregister(),
-                commands::dev::say::say(),
-                commands::dev::dumpconfig(),
-                commands::rng::coinflip(),
-                commands::rng::dice(),
-                commands::rng::ball8(),
-                commands::utils::kick(),
-                commands::utils::avatar(),
-                commands::utils::uptime(),
-                commands::utils::userinfo(),
-                commands::voice::join(),
-                commands::voice::leave(),
-                commands::voice::mute(),
-                commands::voice::unmute(),
-                commands::voice::deafen(),
-                commands::voice::undeafen(),
-                commands::voice::play(),
-                commands::voice::skip(),
-                commands::voice::stop(),
-            ],
+            commands,
             prefix_options,
-            owners: developer_user_ids.clone(),
+            command_check: Some(|ctx| Box::pin(checks::command_check(ctx))),
+            // Cooldowns are started by the pre_command hook, so developers don't hold everyone else up
+            manual_cooldowns: true,
+            on_error: |error| Box::pin(error::on_error(error)),
+            pre_command: |ctx| Box::pin(hooks::pre_command(ctx)),
+            post_command: |ctx| Box::pin(hooks::post_command(ctx)),
+            event_handler: |ctx, event, framework, data| {
+                Box::pin(events::event_handler(ctx, event, framework, data))
+            },
             ..Default::default()
         })
         .setup(|ctx, _ready, framework| {
             Box::pin(async move {
-                poise::builtins::register_globally(ctx, &framework.options().commands).await?;
+                let (registration, developer_guilds) = {
+                    let config = setup_config.read().unwrap();
+                    (
+                        config.command_registration.unwrap_or_default(),
+                        config.developer_guilds.clone().unwrap_or_default(),
+                    )
+                };
+                registration::register(
+                    &ctx.http,
+                    &framework.options().commands,
+                    registration,
+                    developer_guilds,
+                    false,
+                )
+                .await?;
+
+                #[cfg(feature = "rng")]
+                let rng_seed = setup_config.read().unwrap().rng_seed;
+                #[cfg(feature = "rng")]
+                let rng = match rng_seed {
+                    Some(seed) => commands::rng::provider::RngProvider::from_seed(seed),
+                    None => commands::rng::provider::RngProvider::from_entropy(),
+                };
+                #[cfg(all(feature = "rng", feature = "database"))]
+                let rng = rng.restore().await?;
+
+                #[cfg(feature = "dev")]
+                let shutdown = shutdown::Shutdown {
+                    shard_manager: framework.shard_manager().clone(),
+                    #[cfg(feature = "voice")]
+                    songbird: songbird::get(ctx).await.unwrap(),
+                    #[cfg(all(feature = "voice", feature = "database"))]
+                    radios: setup_radio.clone(),
+                };
+
                 Ok(Data {
-                    developers: developer_user_ids,
-                    developer_guilds: developer_guild_ids,
-                    config: conf,
+                    config: setup_config,
+                    #[cfg(feature = "dev")]
+                    config_args: setup_args,
+                    #[cfg(feature = "utils")]
                     start_time: std::time::Instant::now(),
+                    #[cfg(feature = "voice")]
                     http: reqwest::Client::new(),
+                    #[cfg(feature = "voice")]
+                    radio: setup_radio,
+                    #[cfg(feature = "rng")]
+                    rng,
+                    metrics: setup_metrics,
+                    #[cfg(feature = "dev")]
+                    shutdown,
                 })
             })
         })
         .build();
 
-    let client = serenity::ClientBuilder::new(token, intents)
-        .framework(framework)
-        .register_songbird()
+    let client = serenity::ClientBuilder::new(token, intents).framework(framework);
+    #[cfg(feature = "voice")]
+    let songbird = songbird::Songbird::serenity();
+    #[cfg(feature = "voice")]
+    let client = client.register_songbird_with(songbird.clone());
+    let mut client = client.await.unwrap();
+
+    // Everything below is started before the client, so signals and probes are answered while connecting
+    tokio::spawn(reload_on_sighup(config.clone(), config_args.clone()));
+    let shutdown = shutdown::Shutdown {
+        shard_manager: client.shard_manager.clone(),
+        #[cfg(feature = "voice")]
+        songbird: songbird.clone(),
+        #[cfg(all(feature = "voice", feature = "database"))]
+        radios: radio.clone(),
+    };
+    tokio::spawn(shutdown.on_signal());
+    http::start(
+        metrics_address,
+        api_address,
+        http::HttpState {
+            cache: client.cache.clone(),
+            shard_manager: client.shard_manager.clone(),
+            #[cfg(feature = "voice")]
+            songbird,
+            metrics,
+            config,
+            config_args,
+            #[cfg(feature = "voice")]
+            radio,
+        },
+    )
+    .await;
+
+    let start = async {
+        match (shards.shards, shards.shard_range) {
+            (None, _) => client.start().await,
+            (Some(ShardCount::Auto), _) => client.start_autosharded().await,
+            (Some(ShardCount::Fixed(total)), None) => client.start_shards(total).await,
+            // Serenity runs the last shard of the range as well
+            (Some(ShardCount::Fixed(total)), Some(range)) => {
+                client
+                    .start_shard_range(range.first..range.last, total)
+                    .await
+            }
+        }
+    };
+    let result = tokio::select! {
+        result = start => result,
+        () = shutdown::finished() => Ok(()),
+    };
+    if let Err(e) = result {
+        tracing::error!("the client stopped with an error: {}", e);
+    }
+    shutdown::close().await;
+
+    if shutdown::restart_requested() {
+        shutdown::restart();
+    }
+    tracing::info!("shut down");
+}
+
+/// Exits with an explanation if the shard range doesn't fit in the number of shards
+fn check_shards(shards: ShardOptions) {
+    let Some(range) = shards.shard_range else {
+        return;
+    };
+    match shards.shards {
+        Some(ShardCount::Fixed(total)) if range.last < total => {}
+        Some(ShardCount::Fixed(total)) => {
+            println!(
+                "The shard range {}-{} doesn't fit in {} shards, the last shard is {}. Exiting",
+                range.first,
+                range.last,
+                total,
+                total - 1
+            );
+            std::process::exit(1);
+        }
+        _ => {
+            println!("--shard-range needs --shards to be a number rather than auto. Exiting");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Prints the config that `run` would use with the same arguments, exiting with an error if it's invalid
+fn check_config(config_args: &ConfigArgs) {
+    let (config, problems) = match config::load_unchecked(config_args) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let format = config::config_file(config_args)
+        .as_deref()
+        .map_or(config::ConfigFormat::Json, config::ConfigFormat::from_path);
+    println!("Effective config:\n{}", format.serialize(&config));
+
+    if problems.is_empty() {
+        println!("The config is valid");
+    } else {
+        println!("The config has {} problem(s):", problems.len());
+        for problem in problems {
+            println!("\t{}", problem);
+        }
+        std::process::exit(1);
+    }
+}
+
+/// Connects to the database from the config, falling back to the DATABASE_URL environment variable
+#[cfg(feature = "database")]
+async fn connect_database(config: &Config) {
+    let Some(url) = config
+        .database_url
+        .as_ref()
+        .map(|url| url.expose().clone())
+        .or_else(|| std::env::var("DATABASE_URL").ok())
+    else {
+        tracing::error!("exiting as no database URL was provided");
+        println!(
+            "No database URL was provided.\
+            \nEither set the environment variable DATABASE_URL or set the database_url field in the config file.\
+            \nTerminating"
+        );
+        std::process::exit(1);
+    };
+
+    let options = match url.parse::<sqlx::sqlite::SqliteConnectOptions>() {
+        Ok(o) => o,
+        Err(e) => {
+            tracing::error!("exiting as the database URL is invalid: {}", e);
+            println!("The database URL is invalid. Exiting");
+            std::process::exit(1);
+        }
+    };
+
+    let db = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(options)
         .await;
-    client.unwrap().start().await.unwrap();
+    let Ok(database) = db else {
+        tracing::error!("exiting as a connection to the database could not be created");
+        println!("Failed to connect to the database. Exiting");
+        std::process::exit(1);
+    };
+    db::set_database(database);
+
+    if let Err(e) = db::migrate().await {
+        tracing::error!("exiting as the database could not be migrated: {}", e);
+        println!("Failed to update the database schema. Exiting");
+        std::process::exit(1);
+    }
 }
 
-fn get_token(options: TokenOptions) -> Result<String, ()> {
+/// The systemd credential the token is read from when nothing else is specified
+const DEFAULT_TOKEN_CREDENTIAL: &str = "discord_token";
+
+/// The path of a credential passed in by systemd with `LoadCredential=`
+fn credential_path(name: &str) -> Option<std::path::PathBuf> {
+    let dir = std::env::var_os("CREDENTIALS_DIRECTORY")?;
+    Some(std::path::Path::new(&dir).join(name))
+}
+
+fn read_credential(name: &str) -> Result<String, ()> {
+    let Some(path) = credential_path(name) else {
+        tracing::error!(
+            "failed to read credential {}: CREDENTIALS_DIRECTORY is not set",
+            name
+        );
+        return Err(());
+    };
+
+    tracing::info!("reading token from credential: {}", name);
+    match std::fs::read_to_string(path) {
+        Ok(token) => Ok(token.trim().to_string()),
+        Err(e) => {
+            tracing::error!("failed to read token from credential: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Reads the token from wherever the CLI arguments say, or the config file if they don't specify anywhere
+fn get_token(options: TokenOptions, config_token: Option<String>) -> Result<String, ()> {
     if let Some(t) = options.token {
         tracing::info!("a token was provided directly to the command, it will be used");
-        return Ok(t);
+        Ok(t)
     } else if let Some(t) = options.token_var {
         tracing::info!("reading token from environment variable: {}", t);
         match std::env::var(t) {
             Ok(token) => Ok(token),
             Err(e) => {
                 tracing::error!("failed to read token from environment variable: {}", e);
-                return Err(());
+                Err(())
             }
         }
     } else if let Some(t) = options.token_file {
@@ -201,138 +486,86 @@ fn get_token(options: TokenOptions) -> Result<String, ()> {
         let t = std::fs::read_to_string(t);
 
         match t {
-            Ok(token) => Ok(token),
+            // Editors usually end files with a newline, which isn't part of the token
+            Ok(token) => Ok(token.trim().to_string()),
             Err(e) => {
                 tracing::error!("failed to read token from file: {}", e);
-                return Err(());
+                Err(())
             }
         }
+    } else if let Some(name) = options.token_credential {
+        read_credential(&name)
+    } else if credential_path(DEFAULT_TOKEN_CREDENTIAL).is_some_and(|p| p.exists()) {
+        read_credential(DEFAULT_TOKEN_CREDENTIAL)
+    } else if let Some(t) = config_token {
+        tracing::info!("using the token from the config file");
+        Ok(t)
     } else {
         match std::env::var("DISCORD_TOKEN") {
             Ok(token) => Ok(token),
             Err(e) => {
                 tracing::error!("failed to read token from DISCORD_TOKEN: {}", e);
-                return Err(());
+                Err(())
             }
         }
     }
 }
 
-fn merge_config_cli_args(
-    config: &mut Config,
-    prefix: Option<String>,
-    extra_prefixes: Vec<String>,
-    mention_as_prefix: bool,
-    allow_bot_messages: bool,
-    case_sensitive: bool,
-    allow_self_messages: bool,
-    developer_id: Vec<String>,
-    developer_guild: Vec<String>,
-) {
-    // Create a list of all prefixes, with the main prefix first
-    let mut prefixes = Vec::new();
-    if let Some(p) = prefix.clone() {
-        prefixes.push(p);
-    }
-    if let Some(p) = config.prefixes.clone() {
-        prefixes.extend(p);
-    }
-    prefixes.extend(extra_prefixes);
-    if prefix.is_some() {
-        config.prefixes = Some(vec![prefix.unwrap()]);
-    }
-
-    config.prefixes = Some(prefixes);
-
-    // CLI priority
-    if mention_as_prefix {
-        config.mention_as_prefix = Some(true);
-    }
+/// Reloads the config whenever the process receives SIGHUP
+async fn reload_on_sighup(config: SharedConfig, config_args: ConfigArgs) {
+    use tokio::signal::unix::{signal, SignalKind};
 
-    if allow_bot_messages {
-        config.allow_bot_messages = Some(true);
-    }
-
-    if case_sensitive {
-        config.case_sensitive = Some(true);
-    }
-
-    if allow_self_messages {
-        config.allow_self_messages = Some(true);
-    }
-
-    // Merge developer IDs
-    let mut dev_ids = config.developers.clone().unwrap_or_default();
-
-    for id in developer_id {
-        match id.parse::<u64>() {
-            Ok(id) => {
-                dev_ids.insert(serenity::UserId::new(id));
-            }
-            Err(e) => {
-                tracing::error!("failed to parse developer ID: {}, skipping", e);
-            }
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::error!(
+                "failed to listen for SIGHUP, the config can't be reloaded with it: {}",
+                e
+            );
+            return;
         }
-    }
-    config.developers = Some(dev_ids);
-
-    // Merge developer guilds
-    let mut dev_guilds = config.developer_guilds.clone().unwrap_or_default();
+    };
 
-    for id in developer_guild {
-        match id.parse::<u64>() {
-            Ok(id) => {
-                dev_guilds.insert(serenity::GuildId::new(id));
-            }
-            Err(e) => {
-                tracing::error!("failed to parse developer guild ID: {}, skipping", e);
+    while hangup.recv().await.is_some() {
+        tracing::info!("received SIGHUP, reloading the config");
+        match config::reload(&config, &config_args) {
+            Ok(changes) if changes.is_empty() => tracing::info!("the config is unchanged"),
+            Ok(changes) => {
+                for change in changes {
+                    tracing::info!("config changed: {}", change);
+                }
             }
+            Err(e) => tracing::error!(
+                "failed to reload the config, keeping the current one: {}",
+                e
+            ),
         }
     }
-
-    config.developer_guilds = Some(dev_guilds);
 }
 
-fn handle_prefixes(
-    config: &Config,
-    // prefix: Option<String>,
-    // extra_prefixes: Vec<String>,
-    // mention_as_prefix: bool,
-    // allow_bot_messages: bool,
-    // case_sensitive: bool,
-    // allow_self_messages: bool,
-) -> PrefixFrameworkOptions<Data, Error> {
-    // Ensure we have at least one prefix
-    if config.prefixes.is_none() {
-        tracing::error!("exiting as no prefix was provided");
-        println!(
-            "No prefix was provided.\
-            \nEither set the --prefix argument or provide a config file with the prefix field.\
-            \nTerminating"
-        );
-        std::process::exit(1);
-    }
+/// Strips any of the configured prefixes from a message
+///
+/// This reads the prefixes on every message so that a reloaded config takes effect immediately
+fn strip_prefix<'a>(
+    _ctx: &'a serenity::Context,
+    msg: &'a serenity::Message,
+    data: &'a Data,
+) -> BoxFuture<'a, Result<Option<(&'a str, &'a str)>, Error>> {
+    Box::pin(async move {
+        let config = data.config.read().unwrap();
+        let prefix = config
+            .prefixes
+            .iter()
+            .flatten()
+            .find(|p| msg.content.starts_with(p.as_str()));
+
+        Ok(prefix.map(|p| msg.content.split_at(p.len())))
+    })
+}
 
-    let main_prefix = config
-        .prefixes
-        .clone()
-        .expect("we checked there is at least 1 prefix")
-        .first()
-        .unwrap()
-        .clone();
-    let extra_prefixes = config
-        .prefixes
-        .clone()
-        .expect("we checked there is at least 1 prefix")
-        .into_iter()
-        .skip(1)
-        .map(|s| poise::Prefix::Literal(s.leak()))
-        .collect();
-
-    // Any of the following being true means they were explicitly set by the user in CLI, so we can use them as is. If they're false we check the config
+fn handle_prefixes(config: &Config) -> PrefixFrameworkOptions<Data, Error> {
     PrefixFrameworkOptions {
-        prefix: Some(main_prefix),
-        additional_prefixes: extra_prefixes,
+        stripped_dynamic_prefix: Some(strip_prefix),
         execute_self_messages: config.allow_self_messages.unwrap_or(false),
         ignore_bots: !config.allow_bot_messages.unwrap_or(false),
         case_insensitive_commands: !config.case_sensitive.unwrap_or(false),
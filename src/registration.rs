@@ -0,0 +1,142 @@
+use poise::serenity_prelude as serenity;
+use serde_json::Value;
+
+use crate::{config::CommandRegistration, Data, Error};
+
+/// Fields that change how a command appears in Discord, everything else is set by Discord itself
+const COMMAND_FIELDS: &[&str] = &[
+    "type",
+    "name",
+    "description",
+    "options",
+    "default_member_permissions",
+    "dm_permission",
+    "nsfw",
+];
+
+/// Fields of command options that change how they appear in Discord
+const OPTION_FIELDS: &[&str] = &[
+    "type",
+    "name",
+    "description",
+    "required",
+    "choices",
+    "options",
+    "channel_types",
+    "min_value",
+    "max_value",
+    "min_length",
+    "max_length",
+    "autocomplete",
+    "value",
+];
+
+/// Registers the application commands as configured, skipping anything that's already up to date
+///
+/// With `force`, the commands are pushed even if they haven't changed
+pub async fn register(
+    http: &serenity::Http,
+    commands: &[poise::Command<Data, Error>],
+    mode: CommandRegistration,
+    developer_guilds: impl IntoIterator<Item = serenity::GuildId>,
+    force: bool,
+) -> Result<(), serenity::Error> {
+    let create_commands = poise::builtins::create_application_commands(commands);
+
+    match mode {
+        CommandRegistration::Off => {
+            tracing::info!("command registration is turned off, skipping it");
+        }
+        CommandRegistration::Global => {
+            let existing = serenity::Command::get_global_commands(http).await?;
+            if !force && unchanged(&existing, &create_commands) {
+                tracing::info!("global commands are up to date, skipping registration");
+                return Ok(());
+            }
+
+            tracing::info!("registering {} commands globally", create_commands.len());
+            serenity::Command::set_global_commands(http, create_commands).await?;
+        }
+        CommandRegistration::DeveloperGuilds => {
+            for guild_id in developer_guilds {
+                let existing = guild_id.get_commands(http).await?;
+                if !force && unchanged(&existing, &create_commands) {
+                    tracing::info!("commands in guild: {} are up to date, skipping", guild_id);
+                    continue;
+                }
+
+                tracing::info!(
+                    "registering {} commands in guild: {}",
+                    create_commands.len(),
+                    guild_id
+                );
+                guild_id.set_commands(http, create_commands.clone()).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the registered commands match the ones that would be created
+fn unchanged(existing: &[serenity::Command], create: &[serenity::CreateCommand]) -> bool {
+    let signatures = |values: Vec<Value>| {
+        let mut signatures: Vec<_> = values
+            .into_iter()
+            .map(|v| {
+                let mut v = normalise(v, COMMAND_FIELDS);
+                // Slash commands are type 1, which is left out when creating them
+                if v["type"] == 1 {
+                    v.as_object_mut().unwrap().remove("type");
+                }
+                v
+            })
+            .collect();
+        signatures.sort_by_key(|v| v["name"].to_string());
+        signatures
+    };
+
+    let existing = existing
+        .iter()
+        .map(|c| serde_json::to_value(c).expect("commands can always be serialized"))
+        .collect();
+    let create = create
+        .iter()
+        .map(|c| serde_json::to_value(c).expect("commands can always be serialized"))
+        .collect();
+
+    signatures(existing) == signatures(create)
+}
+
+/// Keeps only the given fields, dropping those that are set to what Discord uses by default
+fn normalise(value: Value, fields: &[&str]) -> Value {
+    let Value::Object(map) = value else {
+        return value;
+    };
+
+    let map = map
+        .into_iter()
+        .filter(|(key, _)| fields.contains(&key.as_str()))
+        .filter_map(|(key, value)| {
+            let value = match (key.as_str(), value) {
+                ("options" | "choices", Value::Array(items)) => Value::Array(
+                    items
+                        .into_iter()
+                        .map(|item| normalise(item, OPTION_FIELDS))
+                        .collect(),
+                ),
+                (_, value) => value,
+            };
+
+            let is_default = match (key.as_str(), &value) {
+                ("dm_permission", Value::Bool(allowed)) => *allowed,
+                (_, Value::Null) | (_, Value::Bool(false)) => true,
+                (_, Value::Array(items)) => items.is_empty(),
+                _ => false,
+            };
+            (!is_default).then_some((key, value))
+        })
+        .collect();
+
+    Value::Object(map)
+}
@@ -0,0 +1,48 @@
+use std::{cell::Cell, fmt};
+
+thread_local! {
+    static EXPOSE_SECRETS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// A value that's redacted whenever it's serialized or printed, so it can't leak through
+/// `dumpconfig` or the logs
+///
+/// Wrap config fields holding tokens, passwords or URLs with credentials in this. The real value is
+/// only serialized inside [`exposed`]
+#[derive(Clone, PartialEq, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Secret(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[redacted]")
+    }
+}
+
+impl<T: serde::Serialize> serde::Serialize for Secret<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if EXPOSE_SECRETS.get() {
+            self.0.serialize(serializer)
+        } else {
+            serializer.serialize_str("[redacted]")
+        }
+    }
+}
+
+/// Runs `f` with secrets serialized as their real values, for writing config files or comparing configs
+pub fn exposed<R>(f: impl FnOnce() -> R) -> R {
+    let previous = EXPOSE_SECRETS.replace(true);
+    let result = f();
+    EXPOSE_SECRETS.set(previous);
+    result
+}
@@ -0,0 +1,207 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use poise::serenity_prelude as serenity;
+use tokio::sync::Notify;
+
+#[cfg(all(feature = "voice", feature = "database"))]
+use crate::{
+    commands::voice::{radio::RadioGuilds, QueuedSong},
+    db::saved_queues,
+};
+
+/// How long saving queues and leaving calls can take before the bot exits anyway
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(15);
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+static RESTART: AtomicBool = AtomicBool::new(false);
+static FINISHED: Notify = Notify::const_new();
+
+/// Everything needed to shut the bot down, shared by the signal handler and the developer commands
+#[derive(Clone)]
+pub struct Shutdown {
+    pub shard_manager: Arc<serenity::ShardManager>,
+    #[cfg(feature = "voice")]
+    pub songbird: Arc<songbird::Songbird>,
+    /// Radios aren't saved with the queues, as they come back on by themselves
+    #[cfg(all(feature = "voice", feature = "database"))]
+    pub radios: RadioGuilds,
+}
+
+impl Shutdown {
+    /// Shuts down when the process receives SIGINT or SIGTERM
+    pub async fn on_signal(self) {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let (mut interrupt, mut terminate) = match (
+            signal(SignalKind::interrupt()),
+            signal(SignalKind::terminate()),
+        ) {
+            (Ok(i), Ok(t)) => (i, t),
+            (Err(e), _) | (_, Err(e)) => {
+                tracing::error!("failed to listen for shutdown signals: {}", e);
+                return;
+            }
+        };
+
+        tokio::select! {
+            _ = interrupt.recv() => tracing::info!("received SIGINT"),
+            _ = terminate.recv() => tracing::info!("received SIGTERM"),
+        }
+        self.run(false).await;
+    }
+
+    /// Saves the queues, leaves every call and disconnects from the gateway
+    ///
+    /// The database is left open for [`close`], as commands can still be running until the shards
+    /// have stopped. With `restart`, the bot starts itself again once the client has stopped. Only
+    /// the first call does anything, as the bot is already on its way down after that
+    pub async fn run(&self, restart: bool) {
+        if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        RESTART.store(restart, Ordering::SeqCst);
+        tracing::info!("shutting down");
+
+        #[cfg(feature = "voice")]
+        let cleanup = async {
+            #[cfg(feature = "database")]
+            save_queues(&self.songbird, &self.radios).await;
+            let guilds: Vec<_> = self.songbird.iter().map(|(guild_id, _)| guild_id).collect();
+            for guild_id in guilds {
+                if let Err(e) = self.songbird.remove(guild_id).await {
+                    tracing::warn!("failed to leave the call in guild {}: {}", guild_id.0, e);
+                }
+            }
+        };
+        #[cfg(not(feature = "voice"))]
+        let cleanup = async {};
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, cleanup)
+            .await
+            .is_err()
+        {
+            tracing::warn!(
+                "cleaning up took longer than {:?}, disconnecting anyway",
+                SHUTDOWN_TIMEOUT
+            );
+        }
+
+        self.shard_manager.shutdown_all().await;
+        FINISHED.notify_one();
+    }
+}
+
+/// Resolves once a shutdown has disconnected from the gateway
+///
+/// The client normally stops by itself then, but shards that were still starting up aren't stopped
+/// by the shard manager, so this is raced against the client
+pub async fn finished() {
+    FINISHED.notified().await
+}
+
+/// Closes the database, once the client has stopped and nothing can be using it
+pub async fn close() {
+    #[cfg(feature = "database")]
+    crate::db::get_database().close().await;
+}
+
+/// Whether the bot should start itself again after shutting down
+pub fn restart_requested() -> bool {
+    RESTART.load(Ordering::SeqCst)
+}
+
+/// Replaces the process with a new one, started with the same arguments
+pub fn restart() -> ! {
+    use std::os::unix::process::CommandExt;
+
+    tracing::info!("restarting");
+    let error = match std::env::current_exe() {
+        Ok(exe) => std::process::Command::new(exe)
+            .args(std::env::args_os().skip(1))
+            .exec(),
+        Err(e) => e,
+    };
+    tracing::error!("failed to restart: {}", error);
+    std::process::exit(1);
+}
+
+/// Saves the queue of every call that isn't a radio, so it can be restored on the next start
+#[cfg(all(feature = "voice", feature = "database"))]
+async fn save_queues(manager: &songbird::Songbird, radios: &RadioGuilds) {
+    for (guild_id, call) in manager.iter() {
+        let guild_id = serenity::GuildId::new(guild_id.0.get());
+        if radios.read().unwrap().contains_key(&guild_id) {
+            continue;
+        }
+
+        let (channel_id, tracks) = {
+            let call = call.lock().await;
+            let channel_id = call
+                .current_connection()
+                .and_then(|connection| connection.channel_id);
+            (channel_id, call.queue().current_queue())
+        };
+        let Some(channel_id) = channel_id else {
+            continue;
+        };
+
+        let mut songs = Vec::new();
+        for track in tracks {
+            // Tracks queued by something other than `play` can't be saved
+            if let Some(song) = track.typemap().read().await.get::<QueuedSong>() {
+                songs.push(song.clone());
+            }
+        }
+        if songs.is_empty() {
+            continue;
+        }
+
+        let channel_id = serenity::ChannelId::new(channel_id.0.get());
+        match saved_queues::save(guild_id, channel_id, &songs).await {
+            Ok(()) => tracing::info!("saved {} songs queued in guild: {}", songs.len(), guild_id),
+            Err(e) => tracing::warn!("failed to save the queue in guild {}: {}", guild_id, e),
+        }
+    }
+}
+
+/// Rejoins the calls that had a queue when the bot shut down and queues the songs again
+#[cfg(all(feature = "voice", feature = "database"))]
+pub async fn restore_queues(
+    manager: Arc<songbird::Songbird>,
+    http: reqwest::Client,
+    guilds: Vec<serenity::GuildId>,
+) {
+    for guild_id in guilds {
+        let (channel_id, songs) = match saved_queues::take(guild_id).await {
+            Ok(Some(queue)) => queue,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!(
+                    "failed to load the saved queue in guild {}: {}",
+                    guild_id,
+                    e
+                );
+                continue;
+            }
+        };
+
+        let call = match manager.join(guild_id, channel_id).await {
+            Ok(call) => call,
+            Err(e) => {
+                tracing::warn!("failed to rejoin the call in guild {}: {}", guild_id, e);
+                continue;
+            }
+        };
+        let mut call = call.lock().await;
+        let count = songs.len();
+        for song in songs {
+            song.enqueue(&mut call, http.clone()).await;
+        }
+        tracing::info!("restored {} songs queued in guild: {}", count, guild_id);
+    }
+}